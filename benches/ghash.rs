@@ -0,0 +1,89 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use mpz_share_conversion_core::fields::{gf2_128::Gf2_128, UniformRand};
+use ole_protocols::{
+    ghash::{ghash, PowerStrategy, Prover, Verifier},
+    polyeval::{power_shares_free_squaring, power_shares_lucas, power_shares_pascal},
+};
+use rand::thread_rng;
+
+// The Pascal triangle for 2^14 blocks would hold about 2^27 field elements, so we stop earlier.
+const MAX_LOG_PASCAL: u32 = 11;
+const MAX_LOG_LUCAS: u32 = 14;
+
+// Run with `--features rayon` to compare against the parallel implementation.
+const MIN_LOG_GHASH: u32 = 12;
+const MAX_LOG_GHASH: u32 = 16;
+
+fn bench_h_power_shares(c: &mut Criterion) {
+    let mut group = c.benchmark_group("h_power_shares");
+    group.sample_size(10);
+
+    let mut rng = thread_rng();
+
+    for log in 4..=MAX_LOG_LUCAS {
+        let block_num = 1 << log;
+
+        let d_powers: Vec<Gf2_128> = (0..=block_num).map(|_| Gf2_128::rand(&mut rng)).collect();
+        let r_power_shares: Vec<Gf2_128> =
+            (0..=block_num).map(|_| Gf2_128::rand(&mut rng)).collect();
+
+        if log <= MAX_LOG_PASCAL {
+            group.bench_with_input(
+                BenchmarkId::new("pascal", block_num),
+                &block_num,
+                |b, &block_num| {
+                    b.iter(|| power_shares_pascal(&d_powers, &r_power_shares, block_num))
+                },
+            );
+        }
+
+        group.bench_with_input(
+            BenchmarkId::new("lucas", block_num),
+            &block_num,
+            |b, &block_num| b.iter(|| power_shares_lucas(&d_powers, &r_power_shares, block_num)),
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("free_squaring", block_num),
+            &block_num,
+            |b, &block_num| {
+                b.iter(|| power_shares_free_squaring(&d_powers, &r_power_shares, block_num))
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn bench_ghash(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ghash");
+    group.sample_size(10);
+
+    let mut rng = thread_rng();
+
+    for log in MIN_LOG_GHASH..=MAX_LOG_GHASH {
+        let block_num = 1 << log;
+
+        let blocks: Vec<Gf2_128> = (0..block_num).map(|_| Gf2_128::rand(&mut rng)).collect();
+        let h1 = Gf2_128::rand(&mut rng);
+        let h2 = Gf2_128::rand(&mut rng);
+
+        for (name, strategy) in [
+            ("full", PowerStrategy::Full),
+            ("free_squaring", PowerStrategy::FreeSquaring),
+        ] {
+            group.bench_with_input(BenchmarkId::new(name, block_num), &blocks, |b, blocks| {
+                b.iter(|| {
+                    let mut prover = Prover::new_with_strategy(blocks.len(), h1, strategy);
+                    let mut verifier = Verifier::new_with_strategy(blocks.len(), h2, strategy);
+                    ghash(blocks, &mut prover, &mut verifier)
+                })
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_h_power_shares, bench_ghash);
+criterion_main!(benches);
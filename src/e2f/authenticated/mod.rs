@@ -0,0 +1,641 @@
+//! A maliciously secure variant of the E2F protocol with authenticated openings.
+//!
+//! Every share of the preprocessing (`a`, `b`, `b'`, `c`, `c'`, `r`, `r^2`), of the input point, of
+//! the square of its x-coordinate and of `x1 * x2` carries an information-theoretic MAC, which is
+//! generated with a [`Vole`]. The party holding the share gets the MAC `m = k + delta * x` and the
+//! other party gets the key `k`, where `delta` is the global key of the other party. Since all
+//! opened values are linear in the authenticated shares, the parties compute the MACs of the opened
+//! shares locally, and every opening is checked against the key. A party which sends a wrong share
+//! would have to guess `delta`, so it causes an abort.
+//!
+//! A MAC only shows that a party opens the share it was authenticated with, not that this share is
+//! correct. So before the handshake the parties check that the authenticated `c`, `c'` and `r^2`
+//! are the products of the authenticated `a`, `b`, `b'` and `r`, by sacrificing a second product
+//! for each of them. In the same way they check the squares of the x-coordinates, which every party
+//! computes on its own, and `x1 * x2` against the authenticated x-coordinates, before any value
+//! depending on them is opened. A party which changed its share of a product aborts the protocol
+//! with [`E2fError::InvalidProduct`].
+//!
+//! The public `t` of the slope formula is a coin toss, where both parties commit to their shares of
+//! `t` before opening them, so a rushing party cannot choose `t`.
+//!
+//! [`Vole`]: crate::func::vole::Vole
+
+mod prover;
+mod sacrifice;
+mod verifier;
+
+use super::{
+    curve::ShortWeierstrassCurve,
+    state::{Handshake5Slope, Preprocess1, Preprocessed},
+    E2fError,
+};
+use crate::func::{
+    commit::{Decommitment, HashCommitment},
+    ole::Ole,
+    vole::Vole,
+    Role,
+};
+use mpz_share_conversion_core::fields::Field;
+use std::ops::{Add, Mul, Neg};
+
+pub use prover::Prover;
+pub use verifier::Verifier;
+
+/// Returns the x-coordinate shares of the sum of the two EC points, or aborts if an opening does
+/// not match its MAC.
+pub fn e2f<C: ShortWeierstrassCurve>(
+    prover_point: (C::Field, C::Field),
+    prover: Prover<Preprocess1<C>, C>,
+    verifier_point: (C::Field, C::Field),
+    verifier: Verifier<Preprocess1<C>, C>,
+) -> Result<(C::Field, C::Field), E2fError> {
+    let mut ole = Ole::default();
+
+    // Preprocessing
+    let prover = prover.preprocess1();
+    let verifier = verifier.preprocess1();
+
+    prover.preprocess2_ole_input(&mut ole);
+    verifier.preprocess2_ole_input(&mut ole);
+
+    let prover = prover.preprocess2_ole_output(&mut ole);
+    let verifier = verifier.preprocess2_ole_output(&mut ole);
+
+    let prover = prover.preprocess3();
+    let verifier = verifier.preprocess3();
+
+    let mut prover = prover.preprocess4();
+    let mut verifier = verifier.preprocess4();
+
+    check_preprocessing(&mut prover, &mut verifier, &mut ole)?;
+
+    // Handshake
+    let prover = prover.handshake5_input_ec(prover_point)?;
+    let verifier = verifier.handshake5_input_ec(verifier_point)?;
+
+    prover.handshake5_ole_input(&mut ole);
+    verifier.handshake5_ole_input(&mut ole);
+
+    // Both parties commit to their share of `t` before opening it
+    let t_commitment_prover = prover.handshake5_t_commit();
+    let t_commitment_verifier = verifier.handshake5_t_commit();
+
+    let t_decommitment_prover = prover.handshake5_t_share_open();
+    let t_decommitment_verifier = verifier.handshake5_t_share_open();
+
+    let mut prover =
+        prover.handshake5_ole_output(&mut ole, t_commitment_verifier, t_decommitment_verifier)?;
+    let mut verifier =
+        verifier.handshake5_ole_output(&mut ole, t_commitment_prover, t_decommitment_prover)?;
+
+    check_handshake(&mut prover, &mut verifier, &mut ole)?;
+
+    let varepsilon1_share_prover = prover.handshake5_varepsilon1_share_open();
+    let varepsilon1_share_verifier = verifier.handshake5_varepsilon1_share_open();
+
+    let prover = prover.handshake5_set_omega(varepsilon1_share_verifier)?;
+    let verifier = verifier.handshake5_set_omega(varepsilon1_share_prover)?;
+
+    let omega_share_prover = prover.handshake6_omega_share_open();
+    let omega_share_verifier = verifier.handshake6_omega_share_open();
+
+    let varepsilon2_share_prover = prover.handshake6_varepsilon2_share_open();
+    let varepsilon2_share_verifier = verifier.handshake6_varepsilon2_share_open();
+
+    let prover = prover.handshake6_set_eta(omega_share_verifier, varepsilon2_share_verifier)?;
+    let verifier = verifier.handshake6_set_eta(omega_share_prover, varepsilon2_share_prover)?;
+
+    let varepsilon3_share_prover = prover.handshake7_varepsilon3_share_open();
+    let varepsilon3_share_verifier = verifier.handshake7_varepsilon3_share_open();
+
+    let prover = prover.handshake7_set_z1(varepsilon3_share_verifier)?;
+    let verifier = verifier.handshake7_set_z2(varepsilon3_share_prover)?;
+
+    // Output
+    let z1 = prover.handshake8_z1_open();
+    let z2 = verifier.handshake8_z2_open();
+
+    Ok((z1.share, z2.share))
+}
+
+/// Authenticates the preprocessed shares and checks the products `c`, `c'` and `r^2` by
+/// sacrificing a second product for each of them.
+fn check_preprocessing<C: ShortWeierstrassCurve>(
+    prover: &mut Prover<Preprocessed<C>, C>,
+    verifier: &mut Verifier<Preprocessed<C>, C>,
+    ole: &mut Ole<C::Field>,
+) -> Result<(), E2fError> {
+    // The VOLEs authenticating the shares of the prover and of the verifier
+    let mut vole_prover = Vole::default();
+    let mut vole_verifier = Vole::default();
+
+    prover.preprocess5_ole_input(ole);
+    verifier.preprocess5_ole_input(ole);
+
+    prover.preprocess5_ole_output(ole);
+    verifier.preprocess5_ole_output(ole);
+
+    prover.preprocess5_vole_input(&mut vole_prover, &mut vole_verifier);
+    verifier.preprocess5_vole_input(&mut vole_prover, &mut vole_verifier);
+
+    prover.preprocess5_vole_output(&mut vole_prover, &mut vole_verifier);
+    verifier.preprocess5_vole_output(&mut vole_prover, &mut vole_verifier);
+
+    // Both parties commit to their share of the challenge before opening it
+    let s_commitment_prover = prover.preprocess6_s_commit();
+    let s_commitment_verifier = verifier.preprocess6_s_commit();
+
+    let s_decommitment_prover = prover.preprocess6_s_open();
+    let s_decommitment_verifier = verifier.preprocess6_s_open();
+
+    let rho_shares_prover =
+        prover.preprocess6_rho_shares_open(s_commitment_verifier, s_decommitment_verifier)?;
+    let rho_shares_verifier =
+        verifier.preprocess6_rho_shares_open(s_commitment_prover, s_decommitment_prover)?;
+
+    let w_shares_prover = prover.preprocess6_w_shares_open(rho_shares_verifier)?;
+    let w_shares_verifier = verifier.preprocess6_w_shares_open(rho_shares_prover)?;
+
+    prover.preprocess6_check(w_shares_verifier)?;
+    verifier.preprocess6_check(w_shares_prover)
+}
+
+/// Authenticates the shares of the EC points, of the squares of the x-coordinates and of
+/// `x1 * x2`, and checks the products by sacrificing a second product for each of them.
+fn check_handshake<C: ShortWeierstrassCurve>(
+    prover: &mut Prover<Handshake5Slope<C>, C>,
+    verifier: &mut Verifier<Handshake5Slope<C>, C>,
+    ole: &mut Ole<C::Field>,
+) -> Result<(), E2fError> {
+    let mut vole_prover = Vole::default();
+    let mut vole_verifier = Vole::default();
+
+    prover.handshake5_sacrifice_ole_input(ole);
+    verifier.handshake5_sacrifice_ole_input(ole);
+
+    prover.handshake5_sacrifice_ole_output(ole);
+    verifier.handshake5_sacrifice_ole_output(ole);
+
+    prover.handshake5_vole_input(&mut vole_prover, &mut vole_verifier);
+    verifier.handshake5_vole_input(&mut vole_prover, &mut vole_verifier);
+
+    prover.handshake5_vole_output(&mut vole_prover, &mut vole_verifier);
+    verifier.handshake5_vole_output(&mut vole_prover, &mut vole_verifier);
+
+    let s_commitment_prover = prover.handshake5_s_commit();
+    let s_commitment_verifier = verifier.handshake5_s_commit();
+
+    let s_decommitment_prover = prover.handshake5_s_open();
+    let s_decommitment_verifier = verifier.handshake5_s_open();
+
+    let rho_shares_prover =
+        prover.handshake5_rho_shares_open(s_commitment_verifier, s_decommitment_verifier)?;
+    let rho_shares_verifier =
+        verifier.handshake5_rho_shares_open(s_commitment_prover, s_decommitment_prover)?;
+
+    let w_shares_prover = prover.handshake5_w_shares_open(rho_shares_verifier)?;
+    let w_shares_verifier = verifier.handshake5_w_shares_open(rho_shares_prover)?;
+
+    prover.handshake5_check(w_shares_verifier)?;
+    verifier.handshake5_check(w_shares_prover)
+}
+
+/// A share, which is sent to the other party, together with its MAC.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AuthShare<F> {
+    pub share: F,
+    pub mac: F,
+}
+
+/// The MAC on a party's own share of a value and the key for the other party's share of it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Auth<F> {
+    pub(crate) mac: F,
+    pub(crate) key: F,
+}
+
+impl<F: Field> Add for Auth<F> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            mac: self.mac + rhs.mac,
+            key: self.key + rhs.key,
+        }
+    }
+}
+
+impl<F: Field> Neg for Auth<F> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            mac: -self.mac,
+            key: -self.key,
+        }
+    }
+}
+
+impl<F: Field> Mul<F> for Auth<F> {
+    type Output = Self;
+
+    fn mul(self, rhs: F) -> Self {
+        Self {
+            mac: self.mac * rhs,
+            key: self.key * rhs,
+        }
+    }
+}
+
+/// The [`Auth`] of both coordinates of a point, or of the denominator and numerator of a fraction.
+pub(crate) type AuthPair<F> = (Auth<F>, Auth<F>);
+
+/// The MACs and keys of the preprocessed values.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AuthValues<F> {
+    pub(crate) a: Auth<F>,
+    pub(crate) b: Auth<F>,
+    pub(crate) b_prime: Auth<F>,
+    pub(crate) c: Auth<F>,
+    pub(crate) c_prime: Auth<F>,
+    pub(crate) r: Auth<F>,
+    pub(crate) r_squared: Auth<F>,
+}
+
+impl<F: Field> AuthValues<F> {
+    /// Takes the MACs and keys in the order `a, b, b', c, c', r, r^2`.
+    pub(crate) fn new(macs: &[F], keys: &[F]) -> Self {
+        assert_eq!(macs.len(), 7);
+        assert_eq!(keys.len(), 7);
+
+        let auth = |i: usize| Auth {
+            mac: macs[i],
+            key: keys[i],
+        };
+
+        Self {
+            a: auth(0),
+            b: auth(1),
+            b_prime: auth(2),
+            c: auth(3),
+            c_prime: auth(4),
+            r: auth(5),
+            r_squared: auth(6),
+        }
+    }
+}
+
+/// Checks the opened share of the party with `role` against the key of the other party.
+pub(crate) fn verify<F: Field>(
+    delta: F,
+    key: F,
+    opened: AuthShare<F>,
+    role: Role,
+) -> Result<(), E2fError> {
+    if opened.mac != key + delta * opened.share {
+        return Err(E2fError::InvalidMac(role));
+    }
+    Ok(())
+}
+
+/// A public random value, to which both parties contribute a share.
+///
+/// Every party commits to its share before the shares are opened, so a rushing party cannot choose
+/// its share depending on the share of the other party.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CoinToss<F: Field> {
+    commitment: HashCommitment,
+    decommitment: Decommitment<F>,
+}
+
+impl<F: Field> CoinToss<F> {
+    pub(crate) fn new(share: F) -> Self {
+        let (commitment, decommitment) = HashCommitment::commit(share);
+
+        Self {
+            commitment,
+            decommitment,
+        }
+    }
+
+    pub(crate) fn commitment(&self) -> HashCommitment {
+        self.commitment
+    }
+
+    pub(crate) fn decommitment(&self) -> Decommitment<F> {
+        self.decommitment
+    }
+
+    /// Checks the opening of the share of the party with `role` and returns the value.
+    pub(crate) fn open(
+        &self,
+        role: Role,
+        commitment: HashCommitment,
+        decommitment: Decommitment<F>,
+    ) -> Result<F, E2fError> {
+        let share = commitment
+            .verify(&decommitment)
+            .map_err(|_| E2fError::InvalidOpening(role))?;
+
+        Ok(self.decommitment.value + share)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::e2f::{
+        curve::{P256Curve, P384Curve},
+        tests::random_points,
+    };
+    use mpz_share_conversion_core::fields::p256::P256;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Opening {
+        Varepsilon1,
+        Omega,
+        Varepsilon2,
+        Varepsilon3,
+        Z,
+    }
+
+    #[test]
+    fn test_e2f_authenticated() {
+        let (prover_ec, verifier_ec) = random_points();
+
+        let prover = Prover::new();
+        let verifier = Verifier::new();
+
+        let (z1, z2) = e2f::<P256Curve>(prover_ec, prover, verifier_ec, verifier).unwrap();
+
+        let x_ec_expected = P256Curve::add(prover_ec, verifier_ec).unwrap();
+        assert_eq!(z1 + z2, x_ec_expected.0);
+    }
+
+    #[test]
+    fn test_e2f_authenticated_p384() {
+        let prover_ec = P384Curve::generator();
+        let verifier_ec = P384Curve::add(prover_ec, prover_ec).unwrap();
+
+        let (z1, z2) =
+            e2f::<P384Curve>(prover_ec, Prover::new(), verifier_ec, Verifier::new()).unwrap();
+        assert_eq!(z1 + z2, P384Curve::add(prover_ec, verifier_ec).unwrap().0);
+    }
+
+    #[test]
+    fn test_e2f_authenticated_invalid_point() {
+        let (prover_ec, verifier_ec) = random_points();
+        let off_curve = (verifier_ec.0, verifier_ec.1 + P256::one());
+
+        assert_eq!(
+            e2f::<P256Curve>(prover_ec, Prover::new(), off_curve, Verifier::new()),
+            Err(E2fError::InvalidPoint)
+        );
+    }
+
+    #[test]
+    fn test_e2f_authenticated_doubling() {
+        let (ec_point, _) = random_points();
+
+        let (z1, z2) =
+            e2f::<P256Curve>(ec_point, Prover::new(), ec_point, Verifier::new()).unwrap();
+        assert_eq!(z1 + z2, P256Curve::add(ec_point, ec_point).unwrap().0);
+    }
+
+    #[test]
+    fn test_e2f_authenticated_point_at_infinity() {
+        let (prover_ec, _) = random_points();
+        let verifier_ec = (prover_ec.0, -prover_ec.1);
+
+        assert_eq!(
+            e2f::<P256Curve>(prover_ec, Prover::new(), verifier_ec, Verifier::new()),
+            Err(E2fError::OmegaZero)
+        );
+    }
+
+    #[test]
+    fn test_e2f_authenticated_wrong_share() {
+        for opening in [
+            Opening::Varepsilon1,
+            Opening::Omega,
+            Opening::Varepsilon2,
+            Opening::Varepsilon3,
+            Opening::Z,
+        ] {
+            let add_one = |share: AuthShare<P256>| AuthShare {
+                share: share.share + P256::one(),
+                mac: share.mac,
+            };
+
+            assert_eq!(
+                e2f_with_tampered_verifier(opening, add_one),
+                Err(E2fError::InvalidMac(Role::Receiver)),
+                "{opening:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_e2f_authenticated_wrong_mac() {
+        // Changing the share and adapting the MAC without knowing delta is caught as well.
+        let tamper = |share: AuthShare<P256>| AuthShare {
+            share: share.share + P256::one(),
+            mac: share.mac + P256::one(),
+        };
+
+        assert_eq!(
+            e2f_with_tampered_verifier(Opening::Z, tamper),
+            Err(E2fError::InvalidMac(Role::Receiver))
+        );
+    }
+
+    #[test]
+    fn test_e2f_authenticated_wrong_product() {
+        // A party which shifts its share of `c`, `c'` or `r^2` is caught before the handshake.
+        let shifts: [fn(&mut Verifier<Preprocessed>); 3] = [
+            |verifier| verifier.inner.state.c = verifier.inner.state.c + P256::one(),
+            |verifier| verifier.inner.state.c_prime = verifier.inner.state.c_prime + P256::one(),
+            |verifier| {
+                verifier.inner.state.r_squared_share =
+                    verifier.inner.state.r_squared_share + P256::one()
+            },
+        ];
+
+        for shift in shifts {
+            let mut ole = Ole::default();
+            let (mut prover, mut verifier) = preprocess(&mut ole);
+            shift(&mut verifier);
+
+            assert_eq!(
+                check_preprocessing(&mut prover, &mut verifier, &mut ole),
+                Err(E2fError::InvalidProduct)
+            );
+        }
+    }
+
+    #[test]
+    fn test_e2f_authenticated_wrong_challenge_opening() {
+        let mut ole = Ole::default();
+        let (mut prover, mut verifier) = preprocess(&mut ole);
+        let mut vole_prover = Vole::default();
+        let mut vole_verifier = Vole::default();
+
+        prover.preprocess5_ole_input(&mut ole);
+        verifier.preprocess5_ole_input(&mut ole);
+        prover.preprocess5_ole_output(&mut ole);
+        verifier.preprocess5_ole_output(&mut ole);
+        prover.preprocess5_vole_input(&mut vole_prover, &mut vole_verifier);
+        verifier.preprocess5_vole_input(&mut vole_prover, &mut vole_verifier);
+        prover.preprocess5_vole_output(&mut vole_prover, &mut vole_verifier);
+        verifier.preprocess5_vole_output(&mut vole_prover, &mut vole_verifier);
+
+        // A rushing verifier sees the prover's share of the challenge before it opens its own.
+        let s_commitment_verifier = verifier.preprocess6_s_commit();
+        let mut s_decommitment_verifier = verifier.preprocess6_s_open();
+        s_decommitment_verifier.value = -prover.preprocess6_s_open().value;
+
+        assert_eq!(
+            prover.preprocess6_rho_shares_open(s_commitment_verifier, s_decommitment_verifier),
+            Err(E2fError::InvalidOpening(Role::Receiver))
+        );
+    }
+
+    #[test]
+    fn test_e2f_authenticated_wrong_t_opening() {
+        let (prover_ec, verifier_ec) = random_points();
+        let mut ole = Ole::default();
+        let (mut prover, mut verifier) = preprocess(&mut ole);
+        check_preprocessing(&mut prover, &mut verifier, &mut ole).unwrap();
+
+        let prover = prover.handshake5_input_ec(prover_ec).unwrap();
+        let verifier = verifier.handshake5_input_ec(verifier_ec).unwrap();
+        prover.handshake5_ole_input(&mut ole);
+        verifier.handshake5_ole_input(&mut ole);
+
+        // A rushing verifier sees the prover's share of `t` before it opens its own, and tries to
+        // make `t` zero.
+        let t_commitment_verifier = verifier.handshake5_t_commit();
+        let mut t_decommitment_verifier = verifier.handshake5_t_share_open();
+        t_decommitment_verifier.value = -prover.handshake5_t_share_open().value;
+
+        assert_eq!(
+            prover
+                .handshake5_ole_output(&mut ole, t_commitment_verifier, t_decommitment_verifier)
+                .err(),
+            Some(E2fError::InvalidOpening(Role::Receiver))
+        );
+    }
+
+    /// Runs the preprocessing up to the authentication of the shares.
+    fn preprocess(ole: &mut Ole<P256>) -> (Prover<Preprocessed>, Verifier<Preprocessed>) {
+        let prover = Prover::new().preprocess1();
+        let verifier = Verifier::new().preprocess1();
+        prover.preprocess2_ole_input(ole);
+        verifier.preprocess2_ole_input(ole);
+        let prover = prover.preprocess2_ole_output(ole).preprocess3();
+        let verifier = verifier.preprocess2_ole_output(ole).preprocess3();
+
+        (prover.preprocess4(), verifier.preprocess4())
+    }
+
+    #[test]
+    fn test_e2f_authenticated_wrong_handshake_product() {
+        // A prover which uses a different x-coordinate in the OLE for `x1 * x2` than the one it
+        // authenticates, or which shifts its share of `x1 * x2`, is caught.
+        let shifts: [fn(&mut Prover<Handshake5Slope>); 2] = [
+            |prover| prover.inner.state.ec_point.0 = prover.inner.state.ec_point.0 + P256::one(),
+            |prover| prover.inner.state.x1_x2_share = prover.inner.state.x1_x2_share + P256::one(),
+        ];
+
+        for shift in shifts {
+            let (prover_ec, verifier_ec) = random_points();
+            let mut ole = Ole::default();
+            let (mut prover, mut verifier) = handshake(prover_ec, verifier_ec, &mut ole).unwrap();
+            shift(&mut prover);
+
+            assert_eq!(
+                check_handshake(&mut prover, &mut verifier, &mut ole),
+                Err(E2fError::InvalidProduct)
+            );
+        }
+    }
+
+    /// Runs the preprocessing and the handshake up to the authentication of the shares.
+    fn handshake(
+        prover_ec: (P256, P256),
+        verifier_ec: (P256, P256),
+        ole: &mut Ole<P256>,
+    ) -> Result<(Prover<Handshake5Slope>, Verifier<Handshake5Slope>), E2fError> {
+        let (mut prover, mut verifier) = preprocess(ole);
+        check_preprocessing(&mut prover, &mut verifier, ole)?;
+
+        let prover = prover.handshake5_input_ec(prover_ec)?;
+        let verifier = verifier.handshake5_input_ec(verifier_ec)?;
+        prover.handshake5_ole_input(ole);
+        verifier.handshake5_ole_input(ole);
+        let t_commitment_prover = prover.handshake5_t_commit();
+        let t_commitment_verifier = verifier.handshake5_t_commit();
+        let t_decommitment_prover = prover.handshake5_t_share_open();
+        let t_decommitment_verifier = verifier.handshake5_t_share_open();
+
+        Ok((
+            prover.handshake5_ole_output(ole, t_commitment_verifier, t_decommitment_verifier)?,
+            verifier.handshake5_ole_output(ole, t_commitment_prover, t_decommitment_prover)?,
+        ))
+    }
+
+    /// Runs the protocol and opens the output, but the verifier tampers with its share of
+    /// `opening`.
+    fn e2f_with_tampered_verifier(
+        opening: Opening,
+        tamper: impl Fn(AuthShare<P256>) -> AuthShare<P256>,
+    ) -> Result<P256, E2fError> {
+        let (prover_ec, verifier_ec) = random_points();
+
+        let mut ole = Ole::default();
+        let (mut prover, mut verifier) = handshake(prover_ec, verifier_ec, &mut ole)?;
+        check_handshake(&mut prover, &mut verifier, &mut ole)?;
+
+        let tamper = |share: AuthShare<P256>, current: Opening| {
+            if current == opening {
+                tamper(share)
+            } else {
+                share
+            }
+        };
+
+        let varepsilon1_share_prover = prover.handshake5_varepsilon1_share_open();
+        let varepsilon1_share_verifier = tamper(
+            verifier.handshake5_varepsilon1_share_open(),
+            Opening::Varepsilon1,
+        );
+        let prover = prover.handshake5_set_omega(varepsilon1_share_verifier)?;
+        let verifier = verifier.handshake5_set_omega(varepsilon1_share_prover)?;
+
+        let omega_share_prover = prover.handshake6_omega_share_open();
+        let omega_share_verifier = tamper(verifier.handshake6_omega_share_open(), Opening::Omega);
+        let varepsilon2_share_prover = prover.handshake6_varepsilon2_share_open();
+        let varepsilon2_share_verifier = tamper(
+            verifier.handshake6_varepsilon2_share_open(),
+            Opening::Varepsilon2,
+        );
+        let prover = prover.handshake6_set_eta(omega_share_verifier, varepsilon2_share_verifier)?;
+        let verifier = verifier.handshake6_set_eta(omega_share_prover, varepsilon2_share_prover)?;
+
+        let varepsilon3_share_prover = prover.handshake7_varepsilon3_share_open();
+        let varepsilon3_share_verifier = tamper(
+            verifier.handshake7_varepsilon3_share_open(),
+            Opening::Varepsilon3,
+        );
+        let prover = prover.handshake7_set_z1(varepsilon3_share_verifier)?;
+        let verifier = verifier.handshake7_set_z2(varepsilon3_share_prover)?;
+
+        let z2 = tamper(verifier.handshake8_z2_open(), Opening::Z);
+        let z = prover.handshake8_open_z(z2)?;
+
+        // Without tampering the opened value is the x-coordinate of the sum.
+        assert_eq!(z, P256Curve::add(prover_ec, verifier_ec).unwrap().0);
+        Ok(z)
+    }
+}
@@ -0,0 +1,544 @@
+//! The prover implementation
+
+use super::sacrifice::{self, Sacrifice};
+use super::{verify, Auth, AuthPair, AuthShare, AuthValues, CoinToss};
+use crate::e2f::{
+    self,
+    curve::{P256Curve, ShortWeierstrassCurve},
+    state::{
+        Handshake5, Handshake5Slope, Handshake6, Handshake7, Handshake8, Preprocess1, Preprocess2,
+        Preprocess3, Preprocess4, Preprocessed,
+    },
+    E2fError,
+};
+use crate::func::{
+    commit::{Decommitment, HashCommitment},
+    ole::Ole,
+    vole::Vole,
+    Role,
+};
+use mpz_share_conversion_core::fields::{Field, UniformRand};
+use rand::thread_rng;
+
+/// The MACs and keys are elements of the field of the curve `C`, which is the curve of the state
+/// `S`.
+#[derive(Debug)]
+pub struct Prover<S = Preprocess1, C: ShortWeierstrassCurve = P256Curve> {
+    pub(crate) inner: e2f::Prover<S>,
+    pub(crate) delta: C::Field,
+    /// The masks `f` of the products which are sacrificed to check `c`, `c'` and `r^2`.
+    pub(crate) sacrifice_masks: [C::Field; 3],
+    /// The masks `f` of the products which are sacrificed to check `x1^2`, `x2^2` and `x1 * x2`.
+    pub(crate) handshake_sacrifice_masks: [C::Field; 3],
+
+    // Preprocess 5 and Handshake 5
+    /// The shares of the sacrificed products `h` of the current check.
+    pub(crate) sacrificed: Option<[C::Field; 3]>,
+    pub(crate) auth: Option<AuthValues<C::Field>>,
+    pub(crate) sacrifice: Option<Sacrifice<C::Field>>,
+
+    // Handshake 5
+    /// The coin toss of the challenge `t`.
+    pub(crate) t_coin_toss: Option<CoinToss<C::Field>>,
+    pub(crate) ec_point_auth: Option<AuthPair<C::Field>>,
+    pub(crate) slope_auth: Option<AuthPair<C::Field>>,
+    pub(crate) omega_auth: Option<Auth<C::Field>>,
+
+    // Handshake 6
+    pub(crate) eta_auth: Option<Auth<C::Field>>,
+
+    // Handshake 7
+    pub(crate) z_auth: Option<Auth<C::Field>>,
+}
+
+impl<C: ShortWeierstrassCurve> Default for Prover<Preprocess1<C>, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Prover<Preprocess1<C>, C> {
+    pub fn new() -> Self {
+        let mut rng = thread_rng();
+
+        Self {
+            inner: e2f::Prover::new(),
+            delta: C::Field::rand(&mut rng),
+            sacrifice_masks: std::array::from_fn(|_| C::Field::rand(&mut rng)),
+            handshake_sacrifice_masks: std::array::from_fn(|_| C::Field::rand(&mut rng)),
+            sacrificed: None,
+            auth: None,
+            sacrifice: None,
+            t_coin_toss: None,
+            ec_point_auth: None,
+            slope_auth: None,
+            omega_auth: None,
+            eta_auth: None,
+            z_auth: None,
+        }
+    }
+
+    pub fn preprocess1(self) -> Prover<Preprocess2<C>, C> {
+        self.transition(|inner| inner.preprocess1())
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Prover<Preprocess2<C>, C> {
+    pub fn preprocess2_ole_input(&self, ole: &mut Ole<C::Field>) {
+        self.inner.preprocess2_ole_input(ole);
+    }
+
+    pub fn preprocess2_ole_output(self, ole: &mut Ole<C::Field>) -> Prover<Preprocess3<C>, C> {
+        self.transition(|inner| inner.preprocess2_ole_output(ole))
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Prover<Preprocess3<C>, C> {
+    pub fn preprocess3(self) -> Prover<Preprocess4<C>, C> {
+        self.transition(|inner| inner.preprocess3())
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Prover<Preprocess4<C>, C> {
+    pub fn preprocess4(self) -> Prover<Preprocessed<C>, C> {
+        self.transition(|inner| inner.preprocess4())
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Prover<Preprocessed<C>, C> {
+    pub fn preprocess5_ole_input(&self, ole: &mut Ole<C::Field>) {
+        let (f, y) = self.sacrifice_factors();
+        ole.input(Role::Sender, sacrifice::ole_input(Role::Sender, f, y));
+    }
+
+    pub fn preprocess5_ole_output(&mut self, ole: &mut Ole<C::Field>) {
+        let (f, y) = self.sacrifice_factors();
+        self.sacrificed = Some(sacrifice::ole_output(f, y, ole.output(Role::Sender)));
+    }
+
+    pub fn preprocess5_vole_input(
+        &self,
+        vole_prover: &mut Vole<C::Field>,
+        vole_verifier: &mut Vole<C::Field>,
+    ) {
+        vole_prover.input_x(self.preprocess5_vole_shares());
+        vole_verifier.input_delta(self.delta);
+    }
+
+    pub fn preprocess5_vole_output(
+        &mut self,
+        vole_prover: &mut Vole<C::Field>,
+        vole_verifier: &mut Vole<C::Field>,
+    ) {
+        let macs = vole_prover.output_macs();
+        let keys = vole_verifier.output_keys();
+
+        let values = sacrifice::auth_values(&self.preprocess5_vole_shares(), &macs, &keys);
+        self.auth = Some(AuthValues::new(&macs[..7], &keys[..7]));
+        self.sacrifice = Some(Sacrifice::new(
+            self.delta,
+            Role::Receiver,
+            sacrifice::preprocess_products(&values),
+        ));
+    }
+
+    pub fn preprocess6_s_commit(&self) -> HashCommitment {
+        self.sacrifice().s_commitment()
+    }
+
+    pub fn preprocess6_s_open(&self) -> Decommitment<C::Field> {
+        self.sacrifice().s_decommitment()
+    }
+
+    /// Checks the opening of the verifier's share of the challenge and opens the shares of `rho`.
+    pub fn preprocess6_rho_shares_open(
+        &mut self,
+        s_commitment: HashCommitment,
+        s_decommitment: Decommitment<C::Field>,
+    ) -> Result<Vec<AuthShare<C::Field>>, E2fError> {
+        self.sacrifice_mut()
+            .rho_shares_open(s_commitment, s_decommitment)
+    }
+
+    pub fn preprocess6_w_shares_open(
+        &mut self,
+        rho_shares: Vec<AuthShare<C::Field>>,
+    ) -> Result<Vec<AuthShare<C::Field>>, E2fError> {
+        self.sacrifice_mut().w_shares_open(&rho_shares)
+    }
+
+    /// Returns [`E2fError::InvalidProduct`] if `c`, `c'` or `r^2` is not the product of the
+    /// authenticated shares.
+    pub fn preprocess6_check(
+        &mut self,
+        w_shares: Vec<AuthShare<C::Field>>,
+    ) -> Result<(), E2fError> {
+        self.sacrifice_mut().check(&w_shares)
+    }
+
+    /// Returns [`E2fError::InvalidPoint`] if `ec_point` is not on the curve.
+    pub fn handshake5_input_ec(
+        self,
+        ec_point: (C::Field, C::Field),
+    ) -> Result<Prover<Handshake5<C>, C>, E2fError> {
+        assert!(
+            self.sacrifice().passed(),
+            "the preprocessed products have not been checked"
+        );
+
+        let mut prover = self.try_transition(|inner| inner.handshake5_input_ec(ec_point))?;
+        prover.t_coin_toss = Some(CoinToss::new(prover.inner.handshake5_t_share_open()));
+
+        Ok(prover)
+    }
+
+    /// Returns the masks of the sacrificed products and the factors they are multiplied with.
+    fn sacrifice_factors(&self) -> ([C::Field; 3], [C::Field; 3]) {
+        let masks = self.inner.state.masks;
+        (self.sacrifice_masks, [masks.b, masks.b_prime, masks.r])
+    }
+
+    /// Returns the shares of `a, b, b', c, c', r, r^2`, of the masks `f` and of the sacrificed
+    /// products `h`.
+    fn preprocess5_vole_shares(&self) -> Vec<C::Field> {
+        let state = &self.inner.state;
+        let sacrificed = self
+            .sacrificed
+            .expect("the sacrificed products should be set");
+
+        [
+            [
+                state.masks.a,
+                state.masks.b,
+                state.masks.b_prime,
+                state.c,
+                state.c_prime,
+                state.masks.r,
+                state.r_squared_share,
+            ]
+            .as_slice(),
+            &self.sacrifice_masks,
+            &sacrificed,
+        ]
+        .concat()
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Prover<Handshake5<C>, C> {
+    pub fn handshake5_ole_input(&self, ole: &mut Ole<C::Field>) {
+        self.inner.handshake5_ole_input(ole);
+    }
+
+    pub fn handshake5_t_commit(&self) -> HashCommitment {
+        self.t_coin_toss().commitment()
+    }
+
+    pub fn handshake5_t_share_open(&self) -> Decommitment<C::Field> {
+        self.t_coin_toss().decommitment()
+    }
+
+    /// Checks the opening of the verifier's share of `t`.
+    pub fn handshake5_ole_output(
+        self,
+        ole: &mut Ole<C::Field>,
+        t_commitment: HashCommitment,
+        t_decommitment: Decommitment<C::Field>,
+    ) -> Result<Prover<Handshake5Slope<C>, C>, E2fError> {
+        let t = self
+            .t_coin_toss()
+            .open(Role::Receiver, t_commitment, t_decommitment)?;
+
+        Ok(self.transition(|inner| inner.handshake5_ole_output(ole, t)))
+    }
+
+    fn t_coin_toss(&self) -> &CoinToss<C::Field> {
+        self.t_coin_toss
+            .as_ref()
+            .expect("the EC point should be set")
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Prover<Handshake5Slope<C>, C> {
+    pub fn handshake5_sacrifice_ole_input(&self, ole: &mut Ole<C::Field>) {
+        let (f, y) = self.handshake5_sacrifice_factors();
+        ole.input(Role::Sender, sacrifice::ole_input(Role::Sender, f, y));
+    }
+
+    pub fn handshake5_sacrifice_ole_output(&mut self, ole: &mut Ole<C::Field>) {
+        let (f, y) = self.handshake5_sacrifice_factors();
+        self.sacrificed = Some(sacrifice::ole_output(f, y, ole.output(Role::Sender)));
+    }
+
+    pub fn handshake5_vole_input(
+        &self,
+        vole_prover: &mut Vole<C::Field>,
+        vole_verifier: &mut Vole<C::Field>,
+    ) {
+        vole_prover.input_x(self.handshake5_vole_shares());
+        vole_verifier.input_delta(self.delta);
+    }
+
+    pub fn handshake5_vole_output(
+        &mut self,
+        vole_prover: &mut Vole<C::Field>,
+        vole_verifier: &mut Vole<C::Field>,
+    ) {
+        let macs = vole_prover.output_macs();
+        let keys = vole_verifier.output_keys();
+
+        let values = sacrifice::auth_values(&self.handshake5_vole_shares(), &macs, &keys);
+        self.sacrifice = Some(Sacrifice::new(
+            self.delta,
+            Role::Receiver,
+            sacrifice::handshake_products(Role::Sender, &values),
+        ));
+
+        let auth = |i: usize| Auth {
+            mac: macs[i],
+            key: keys[i],
+        };
+        let (x, y, x_squared, x1_x2) = (auth(0), auth(1), auth(2), auth(3));
+        let t = self.inner.state.t;
+
+        // The prover's shares are `-x1 + t * y1` and `-y1 + t * (x1^2 + x1_x2_share + a)`, the
+        // verifier's shares are `x2 + t * y2` and `y2 + t * (x2^2 + x1_x2_share)`.
+        let denominator = Auth {
+            mac: -x.mac + t * y.mac,
+            key: x.key + t * y.key,
+        };
+        let numerator = Auth {
+            mac: -y.mac + t * (x_squared.mac + x1_x2.mac),
+            key: y.key + t * (x_squared.key + x1_x2.key),
+        };
+
+        self.ec_point_auth = Some((x, y));
+        self.slope_auth = Some((denominator, numerator));
+    }
+
+    pub fn handshake5_s_commit(&self) -> HashCommitment {
+        self.sacrifice().s_commitment()
+    }
+
+    pub fn handshake5_s_open(&self) -> Decommitment<C::Field> {
+        self.sacrifice().s_decommitment()
+    }
+
+    /// Checks the opening of the verifier's share of the challenge and opens the shares of `rho`.
+    pub fn handshake5_rho_shares_open(
+        &mut self,
+        s_commitment: HashCommitment,
+        s_decommitment: Decommitment<C::Field>,
+    ) -> Result<Vec<AuthShare<C::Field>>, E2fError> {
+        self.sacrifice_mut()
+            .rho_shares_open(s_commitment, s_decommitment)
+    }
+
+    pub fn handshake5_w_shares_open(
+        &mut self,
+        rho_shares: Vec<AuthShare<C::Field>>,
+    ) -> Result<Vec<AuthShare<C::Field>>, E2fError> {
+        self.sacrifice_mut().w_shares_open(&rho_shares)
+    }
+
+    /// Returns [`E2fError::InvalidProduct`] if the authenticated `x1^2`, `x2^2` or `x1 * x2` is
+    /// not the product of the authenticated x-coordinates.
+    pub fn handshake5_check(&mut self, w_shares: Vec<AuthShare<C::Field>>) -> Result<(), E2fError> {
+        self.sacrifice_mut().check(&w_shares)
+    }
+
+    pub fn handshake5_varepsilon1_share_open(&self) -> AuthShare<C::Field> {
+        AuthShare {
+            share: self.inner.handshake5_varepsilon1_share_open(),
+            mac: self.varepsilon1_auth().mac,
+        }
+    }
+
+    pub fn handshake5_set_omega(
+        mut self,
+        varepsilon1_share: AuthShare<C::Field>,
+    ) -> Result<Prover<Handshake6<C>, C>, E2fError> {
+        assert!(
+            self.sacrifice().passed(),
+            "the products of the x-coordinates have not been checked"
+        );
+        self.verify(self.varepsilon1_auth().key, varepsilon1_share)?;
+
+        let varepsilon1 = self.inner.handshake5_varepsilon1_share_open() + varepsilon1_share.share;
+
+        let auth = self.auth.unwrap();
+        self.omega_auth = Some(auth.a * varepsilon1 + auth.c);
+
+        Ok(self.transition(|inner| inner.handshake5_set_omega(varepsilon1)))
+    }
+
+    /// Returns the masks of the sacrificed products and the factors they are multiplied with. The
+    /// prover's shares of `x2` are 0.
+    fn handshake5_sacrifice_factors(&self) -> ([C::Field; 3], [C::Field; 3]) {
+        let (x1, _) = self.inner.state.ec_point;
+        (
+            self.handshake_sacrifice_masks,
+            [x1, C::Field::zero(), C::Field::zero()],
+        )
+    }
+
+    /// Returns the shares of `x`, `y`, `x^2` and `x1 * x2`, of the masks `f` and of the sacrificed
+    /// products `h`.
+    fn handshake5_vole_shares(&self) -> Vec<C::Field> {
+        let (x1, y1) = self.inner.state.ec_point;
+        let x1_x2_share = self.inner.state.x1_x2_share;
+        let sacrificed = self
+            .sacrificed
+            .expect("the sacrificed products should be set");
+
+        [
+            [x1, y1, x1 * x1, x1_x2_share].as_slice(),
+            &self.handshake_sacrifice_masks,
+            &sacrificed,
+        ]
+        .concat()
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Prover<Handshake6<C>, C> {
+    pub fn handshake6_omega_share_open(&self) -> AuthShare<C::Field> {
+        AuthShare {
+            share: self.inner.handshake6_omega_share_open(),
+            mac: self.omega_auth.unwrap().mac,
+        }
+    }
+
+    pub fn handshake6_varepsilon2_share_open(&self) -> AuthShare<C::Field> {
+        AuthShare {
+            share: self.inner.handshake6_varepsilon2_share_open(),
+            mac: self.varepsilon2_auth().mac,
+        }
+    }
+
+    pub fn handshake6_set_eta(
+        self,
+        omega_share: AuthShare<C::Field>,
+        varepsilon2_share: AuthShare<C::Field>,
+    ) -> Result<Prover<Handshake7<C>, C>, E2fError> {
+        self.verify(self.omega_auth.unwrap().key, omega_share)?;
+        self.verify(self.varepsilon2_auth().key, varepsilon2_share)?;
+
+        let omega = self.inner.handshake6_omega_share_open() + omega_share.share;
+        let varepsilon2 = self.inner.handshake6_varepsilon2_share_open() + varepsilon2_share.share;
+
+        let auth = self.auth.unwrap();
+        let mut next = self.try_transition(|inner| inner.handshake6_set_eta(omega, varepsilon2))?;
+        next.eta_auth = Some((auth.a * varepsilon2 + auth.c_prime) * omega.inverse());
+
+        Ok(next)
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Prover<Handshake7<C>, C> {
+    pub fn handshake7_varepsilon3_share_open(&self) -> AuthShare<C::Field> {
+        AuthShare {
+            share: self.inner.handshake7_varepsilon3_share_open(),
+            mac: self.varepsilon3_auth().mac,
+        }
+    }
+
+    pub fn handshake7_set_z1(
+        mut self,
+        varepsilon3_share: AuthShare<C::Field>,
+    ) -> Result<Prover<Handshake8<C>, C>, E2fError> {
+        self.verify(self.varepsilon3_auth().key, varepsilon3_share)?;
+
+        let varepsilon3 = self.inner.handshake7_varepsilon3_share_open() + varepsilon3_share.share;
+
+        // The prover adds the public varepsilon3^2 to its share, which the verifier accounts for
+        // in its key.
+        let two = C::Field::one() + C::Field::one();
+        let auth = self.auth.unwrap();
+        let x = self.ec_point_auth.unwrap().0;
+        self.z_auth = Some(auth.r * (two * varepsilon3) + auth.r_squared + -x);
+
+        Ok(self.transition(|inner| inner.handshake7_set_z1(varepsilon3)))
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Prover<Handshake8<C>, C> {
+    pub fn handshake8_z1_open(&self) -> AuthShare<C::Field> {
+        AuthShare {
+            share: self.inner.handshake8_z1_open(),
+            mac: self.z_auth.unwrap().mac,
+        }
+    }
+
+    /// Checks the opened share of the verifier and returns the x-coordinate.
+    ///
+    /// Only needed if the output is opened, otherwise the parties keep their shares.
+    pub fn handshake8_open_z(&self, z2: AuthShare<C::Field>) -> Result<C::Field, E2fError> {
+        self.verify(self.z_auth.unwrap().key, z2)?;
+
+        Ok(self.inner.handshake8_z1_open() + z2.share)
+    }
+}
+
+impl<S, C: ShortWeierstrassCurve> Prover<S, C> {
+    fn transition<T>(self, f: impl FnOnce(e2f::Prover<S>) -> e2f::Prover<T>) -> Prover<T, C> {
+        Prover {
+            inner: f(self.inner),
+            delta: self.delta,
+            sacrifice_masks: self.sacrifice_masks,
+            handshake_sacrifice_masks: self.handshake_sacrifice_masks,
+            sacrificed: self.sacrificed,
+            auth: self.auth,
+            sacrifice: self.sacrifice,
+            t_coin_toss: self.t_coin_toss,
+            ec_point_auth: self.ec_point_auth,
+            slope_auth: self.slope_auth,
+            omega_auth: self.omega_auth,
+            eta_auth: self.eta_auth,
+            z_auth: self.z_auth,
+        }
+    }
+
+    fn try_transition<T>(
+        self,
+        f: impl FnOnce(e2f::Prover<S>) -> Result<e2f::Prover<T>, E2fError>,
+    ) -> Result<Prover<T, C>, E2fError> {
+        Ok(Prover {
+            inner: f(self.inner)?,
+            delta: self.delta,
+            sacrifice_masks: self.sacrifice_masks,
+            handshake_sacrifice_masks: self.handshake_sacrifice_masks,
+            sacrificed: self.sacrificed,
+            auth: self.auth,
+            sacrifice: self.sacrifice,
+            t_coin_toss: self.t_coin_toss,
+            ec_point_auth: self.ec_point_auth,
+            slope_auth: self.slope_auth,
+            omega_auth: self.omega_auth,
+            eta_auth: self.eta_auth,
+            z_auth: self.z_auth,
+        })
+    }
+
+    fn varepsilon1_auth(&self) -> Auth<C::Field> {
+        self.slope_auth.unwrap().0 + -self.auth.unwrap().b
+    }
+
+    fn varepsilon2_auth(&self) -> Auth<C::Field> {
+        self.slope_auth.unwrap().1 + -self.auth.unwrap().b_prime
+    }
+
+    fn varepsilon3_auth(&self) -> Auth<C::Field> {
+        self.eta_auth.unwrap() + -self.auth.unwrap().r
+    }
+
+    fn sacrifice(&self) -> &Sacrifice<C::Field> {
+        self.sacrifice.as_ref().expect("the VOLE should be run")
+    }
+
+    fn sacrifice_mut(&mut self) -> &mut Sacrifice<C::Field> {
+        self.sacrifice.as_mut().expect("the VOLE should be run")
+    }
+
+    fn verify(&self, key: C::Field, opened: AuthShare<C::Field>) -> Result<(), E2fError> {
+        verify(self.delta, key, opened, Role::Receiver)
+    }
+}
@@ -0,0 +1,305 @@
+//! Checks that authenticated shares multiply correctly by sacrificing a second product.
+//!
+//! To check `z = x * y`, the parties additionally hold an authenticated random `f` and `h = f * y`,
+//! which is computed with an OLE like `z`. Once all of them are authenticated, the parties agree on
+//! a random challenge `s`, open `rho = s * x - f` and then `w = s * z - h - rho * y`, which is
+//! `s * (z - x * y) - (h - f * y)`. A party which shifts its shares of `z` and `h` by `e_z` and
+//! `e_h` makes `w = s * e_z - e_h`, which is only 0 if it guessed `s`. Using a wrong `y` in the OLE
+//! for `h` shifts `w` by a multiple of `f`, which is masked by the other party. Since `f` is only
+//! used once, `rho` hides `x`, and `w` is 0, so opening it reveals nothing.
+//!
+//! Both parties commit to their shares of `s` before opening them, so a rushing party cannot pick
+//! the challenge.
+
+use super::{verify, Auth, AuthShare, CoinToss};
+use crate::e2f::E2fError;
+use crate::func::{
+    commit::{Decommitment, HashCommitment},
+    Role,
+};
+use mpz_share_conversion_core::fields::Field;
+use rand::thread_rng;
+use std::ops::{Add, Mul, Neg};
+
+/// A party's share of a value together with its [`Auth`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct AuthValue<F: Field> {
+    pub(crate) share: F,
+    pub(crate) auth: Auth<F>,
+}
+
+impl<F: Field> AuthValue<F> {
+    fn open(self) -> AuthShare<F> {
+        AuthShare {
+            share: self.share,
+            mac: self.auth.mac,
+        }
+    }
+}
+
+impl<F: Field> Add for AuthValue<F> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            share: self.share + rhs.share,
+            auth: self.auth + rhs.auth,
+        }
+    }
+}
+
+impl<F: Field> Neg for AuthValue<F> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            share: -self.share,
+            auth: -self.auth,
+        }
+    }
+}
+
+impl<F: Field> Mul<F> for AuthValue<F> {
+    type Output = Self;
+
+    fn mul(self, rhs: F) -> Self {
+        Self {
+            share: self.share * rhs,
+            auth: self.auth * rhs,
+        }
+    }
+}
+
+/// A claimed product `z = x * y` and the sacrificed product `h = f * y`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Product<F: Field> {
+    pub(crate) x: AuthValue<F>,
+    pub(crate) y: AuthValue<F>,
+    pub(crate) z: AuthValue<F>,
+    pub(crate) f: AuthValue<F>,
+    pub(crate) h: AuthValue<F>,
+}
+
+/// One party's view of the check of several products.
+#[derive(Debug)]
+pub(crate) struct Sacrifice<F: Field> {
+    delta: F,
+    /// The role of the other party, whose openings are checked.
+    other: Role,
+    products: Vec<Product<F>>,
+    s_coin_toss: CoinToss<F>,
+    s: Option<F>,
+    rho: Vec<F>,
+    passed: bool,
+}
+
+impl<F: Field> Sacrifice<F> {
+    pub(crate) fn new(delta: F, other: Role, products: Vec<Product<F>>) -> Self {
+        Self {
+            delta,
+            other,
+            products,
+            s_coin_toss: CoinToss::new(F::rand(&mut thread_rng())),
+            s: None,
+            rho: vec![],
+            passed: false,
+        }
+    }
+
+    pub(crate) fn s_commitment(&self) -> HashCommitment {
+        self.s_coin_toss.commitment()
+    }
+
+    pub(crate) fn s_decommitment(&self) -> Decommitment<F> {
+        self.s_coin_toss.decommitment()
+    }
+
+    /// Checks the opening of the other party's share of `s` and returns the shares of `rho`.
+    pub(crate) fn rho_shares_open(
+        &mut self,
+        s_commitment: HashCommitment,
+        s_decommitment: Decommitment<F>,
+    ) -> Result<Vec<AuthShare<F>>, E2fError> {
+        let s = self
+            .s_coin_toss
+            .open(self.other, s_commitment, s_decommitment)?;
+        self.s = Some(s);
+
+        Ok(self
+            .rho_values(s)
+            .into_iter()
+            .map(AuthValue::open)
+            .collect())
+    }
+
+    /// Checks the other party's shares of `rho` and returns the shares of `w`.
+    pub(crate) fn w_shares_open(
+        &mut self,
+        rho_shares: &[AuthShare<F>],
+    ) -> Result<Vec<AuthShare<F>>, E2fError> {
+        let s = self.s.expect("s should be set");
+        let own = self.rho_values(s);
+        assert_eq!(rho_shares.len(), own.len());
+
+        self.rho = own
+            .into_iter()
+            .zip(rho_shares)
+            .map(|(own, &other)| {
+                verify(self.delta, own.auth.key, other, self.other)?;
+                Ok(own.share + other.share)
+            })
+            .collect::<Result<_, E2fError>>()?;
+
+        Ok(self.w_values(s).into_iter().map(AuthValue::open).collect())
+    }
+
+    /// Checks the other party's shares of `w` and that every `w` is 0.
+    pub(crate) fn check(&mut self, w_shares: &[AuthShare<F>]) -> Result<(), E2fError> {
+        let s = self.s.expect("s should be set");
+        let own = self.w_values(s);
+        assert_eq!(w_shares.len(), own.len());
+
+        for (own, &other) in own.into_iter().zip(w_shares) {
+            verify(self.delta, own.auth.key, other, self.other)?;
+
+            if own.share + other.share != F::zero() {
+                return Err(E2fError::InvalidProduct);
+            }
+        }
+
+        self.passed = true;
+        Ok(())
+    }
+
+    pub(crate) fn passed(&self) -> bool {
+        self.passed
+    }
+
+    fn rho_values(&self, s: F) -> Vec<AuthValue<F>> {
+        self.products
+            .iter()
+            .map(|product| product.x * s + -product.f)
+            .collect()
+    }
+
+    fn w_values(&self, s: F) -> Vec<AuthValue<F>> {
+        self.products
+            .iter()
+            .zip(&self.rho)
+            .map(|(product, &rho)| product.z * s + -product.h + -(product.y * rho))
+            .collect()
+    }
+}
+
+/// Returns the OLE input for the sacrificed products `f[i] * y[i]` of shared values.
+pub(crate) fn ole_input<F: Field, const N: usize>(role: Role, f: [F; N], y: [F; N]) -> Vec<F> {
+    match role {
+        Role::Sender => [f, y].concat(),
+        Role::Receiver => [y, f].concat(),
+    }
+}
+
+/// Returns the shares of the sacrificed products `f[i] * y[i]` from the OLE output for
+/// [`ole_input`].
+pub(crate) fn ole_output<F: Field, const N: usize>(
+    f: [F; N],
+    y: [F; N],
+    ole_output: Vec<F>,
+) -> [F; N] {
+    assert_eq!(ole_output.len(), 2 * N);
+
+    std::array::from_fn(|i| f[i] * y[i] + ole_output[i] + ole_output[N + i])
+}
+
+/// Combines the shares with their MACs and keys.
+pub(crate) fn auth_values<F: Field>(shares: &[F], macs: &[F], keys: &[F]) -> Vec<AuthValue<F>> {
+    assert_eq!(shares.len(), macs.len());
+    assert_eq!(shares.len(), keys.len());
+
+    shares
+        .iter()
+        .zip(macs.iter().zip(keys))
+        .map(|(&share, (&mac, &key))| AuthValue {
+            share,
+            auth: Auth { mac, key },
+        })
+        .collect()
+}
+
+/// Returns the checks of `c = a * b`, `c' = a * b'` and `r^2 = r * r` from the values in the
+/// order `a, b, b', c, c', r, r^2, f_c, f_c', f_r, h_c, h_c', h_r`.
+pub(crate) fn preprocess_products<F: Field>(values: &[AuthValue<F>]) -> Vec<Product<F>> {
+    assert_eq!(values.len(), 13);
+
+    let [a, b, b_prime, c, c_prime, r, r_squared] = std::array::from_fn(|i| values[i]);
+    let factors = [(a, b, c), (a, b_prime, c_prime), (r, r, r_squared)];
+
+    factors
+        .into_iter()
+        .enumerate()
+        .map(|(i, (x, y, z))| Product {
+            x,
+            y,
+            z,
+            f: values[7 + i],
+            h: values[10 + i],
+        })
+        .collect()
+}
+
+/// Returns the checks of `x1^2 = x1 * x1`, `x2^2 = x2 * x2` and `x1 * x2` from the values of the
+/// party with `role` in the order `x, y, x^2, x1 * x2, f_a, f_b, f_c, h_a, h_b, h_c`.
+///
+/// Only the party which chose `x` and `x^2` holds a share of them, the share of the other party is
+/// 0, with MAC and key 0 as well.
+pub(crate) fn handshake_products<F: Field>(role: Role, values: &[AuthValue<F>]) -> Vec<Product<F>> {
+    assert_eq!(values.len(), 10);
+
+    let zero = F::zero();
+    let own = |value: AuthValue<F>| AuthValue {
+        share: value.share,
+        auth: Auth {
+            mac: value.auth.mac,
+            key: zero,
+        },
+    };
+    let other = |value: AuthValue<F>| AuthValue {
+        share: zero,
+        auth: Auth {
+            mac: zero,
+            key: value.auth.key,
+        },
+    };
+
+    let (x1, x1_squared, x2, x2_squared) = match role {
+        Role::Sender => (
+            own(values[0]),
+            own(values[2]),
+            other(values[0]),
+            other(values[2]),
+        ),
+        Role::Receiver => (
+            other(values[0]),
+            other(values[2]),
+            own(values[0]),
+            own(values[2]),
+        ),
+    };
+    let factors = [
+        (x1, x1, x1_squared),
+        (x2, x2, x2_squared),
+        (x1, x2, values[3]),
+    ];
+
+    factors
+        .into_iter()
+        .enumerate()
+        .map(|(i, (x, y, z))| Product {
+            x,
+            y,
+            z,
+            f: values[4 + i],
+            h: values[7 + i],
+        })
+        .collect()
+}
@@ -0,0 +1,548 @@
+//! The verifier implementation
+
+use super::sacrifice::{self, Sacrifice};
+use super::{verify, Auth, AuthPair, AuthShare, AuthValues, CoinToss};
+use crate::e2f::{
+    self,
+    curve::{P256Curve, ShortWeierstrassCurve},
+    state::{
+        Handshake5, Handshake5Slope, Handshake6, Handshake7, Handshake8, Preprocess1, Preprocess2,
+        Preprocess3, Preprocess4, Preprocessed,
+    },
+    E2fError,
+};
+use crate::func::{
+    commit::{Decommitment, HashCommitment},
+    ole::Ole,
+    vole::Vole,
+    Role,
+};
+use mpz_share_conversion_core::fields::{Field, UniformRand};
+use rand::thread_rng;
+
+/// The MACs and keys are elements of the field of the curve `C`, which is the curve of the state
+/// `S`.
+#[derive(Debug)]
+pub struct Verifier<S = Preprocess1, C: ShortWeierstrassCurve = P256Curve> {
+    pub(crate) inner: e2f::Verifier<S>,
+    pub(crate) delta: C::Field,
+    /// The masks `f` of the products which are sacrificed to check `c`, `c'` and `r^2`.
+    pub(crate) sacrifice_masks: [C::Field; 3],
+    /// The masks `f` of the products which are sacrificed to check `x1^2`, `x2^2` and `x1 * x2`.
+    pub(crate) handshake_sacrifice_masks: [C::Field; 3],
+
+    // Preprocess 5 and Handshake 5
+    /// The shares of the sacrificed products `h` of the current check.
+    pub(crate) sacrificed: Option<[C::Field; 3]>,
+    pub(crate) auth: Option<AuthValues<C::Field>>,
+    pub(crate) sacrifice: Option<Sacrifice<C::Field>>,
+
+    // Handshake 5
+    /// The coin toss of the challenge `t`.
+    pub(crate) t_coin_toss: Option<CoinToss<C::Field>>,
+    pub(crate) ec_point_auth: Option<AuthPair<C::Field>>,
+    pub(crate) slope_auth: Option<AuthPair<C::Field>>,
+    pub(crate) omega_auth: Option<Auth<C::Field>>,
+
+    // Handshake 6
+    pub(crate) eta_auth: Option<Auth<C::Field>>,
+
+    // Handshake 7
+    pub(crate) z_auth: Option<Auth<C::Field>>,
+}
+
+impl<C: ShortWeierstrassCurve> Default for Verifier<Preprocess1<C>, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Verifier<Preprocess1<C>, C> {
+    pub fn new() -> Self {
+        let mut rng = thread_rng();
+
+        Self {
+            inner: e2f::Verifier::new(),
+            delta: C::Field::rand(&mut rng),
+            sacrifice_masks: std::array::from_fn(|_| C::Field::rand(&mut rng)),
+            handshake_sacrifice_masks: std::array::from_fn(|_| C::Field::rand(&mut rng)),
+            sacrificed: None,
+            auth: None,
+            sacrifice: None,
+            t_coin_toss: None,
+            ec_point_auth: None,
+            slope_auth: None,
+            omega_auth: None,
+            eta_auth: None,
+            z_auth: None,
+        }
+    }
+
+    pub fn preprocess1(self) -> Verifier<Preprocess2<C>, C> {
+        self.transition(|inner| inner.preprocess1())
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Verifier<Preprocess2<C>, C> {
+    pub fn preprocess2_ole_input(&self, ole: &mut Ole<C::Field>) {
+        self.inner.preprocess2_ole_input(ole);
+    }
+
+    pub fn preprocess2_ole_output(self, ole: &mut Ole<C::Field>) -> Verifier<Preprocess3<C>, C> {
+        self.transition(|inner| inner.preprocess2_ole_output(ole))
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Verifier<Preprocess3<C>, C> {
+    pub fn preprocess3(self) -> Verifier<Preprocess4<C>, C> {
+        self.transition(|inner| inner.preprocess3())
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Verifier<Preprocess4<C>, C> {
+    pub fn preprocess4(self) -> Verifier<Preprocessed<C>, C> {
+        self.transition(|inner| inner.preprocess4())
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Verifier<Preprocessed<C>, C> {
+    pub fn preprocess5_ole_input(&self, ole: &mut Ole<C::Field>) {
+        let (f, y) = self.sacrifice_factors();
+        ole.input(Role::Receiver, sacrifice::ole_input(Role::Receiver, f, y));
+    }
+
+    pub fn preprocess5_ole_output(&mut self, ole: &mut Ole<C::Field>) {
+        let (f, y) = self.sacrifice_factors();
+        self.sacrificed = Some(sacrifice::ole_output(f, y, ole.output(Role::Receiver)));
+    }
+
+    pub fn preprocess5_vole_input(
+        &self,
+        vole_prover: &mut Vole<C::Field>,
+        vole_verifier: &mut Vole<C::Field>,
+    ) {
+        vole_prover.input_delta(self.delta);
+        vole_verifier.input_x(self.preprocess5_vole_shares());
+    }
+
+    pub fn preprocess5_vole_output(
+        &mut self,
+        vole_prover: &mut Vole<C::Field>,
+        vole_verifier: &mut Vole<C::Field>,
+    ) {
+        let keys = vole_prover.output_keys();
+        let macs = vole_verifier.output_macs();
+
+        let values = sacrifice::auth_values(&self.preprocess5_vole_shares(), &macs, &keys);
+        self.auth = Some(AuthValues::new(&macs[..7], &keys[..7]));
+        self.sacrifice = Some(Sacrifice::new(
+            self.delta,
+            Role::Sender,
+            sacrifice::preprocess_products(&values),
+        ));
+    }
+
+    pub fn preprocess6_s_commit(&self) -> HashCommitment {
+        self.sacrifice().s_commitment()
+    }
+
+    pub fn preprocess6_s_open(&self) -> Decommitment<C::Field> {
+        self.sacrifice().s_decommitment()
+    }
+
+    /// Checks the opening of the prover's share of the challenge and opens the shares of `rho`.
+    pub fn preprocess6_rho_shares_open(
+        &mut self,
+        s_commitment: HashCommitment,
+        s_decommitment: Decommitment<C::Field>,
+    ) -> Result<Vec<AuthShare<C::Field>>, E2fError> {
+        self.sacrifice_mut()
+            .rho_shares_open(s_commitment, s_decommitment)
+    }
+
+    pub fn preprocess6_w_shares_open(
+        &mut self,
+        rho_shares: Vec<AuthShare<C::Field>>,
+    ) -> Result<Vec<AuthShare<C::Field>>, E2fError> {
+        self.sacrifice_mut().w_shares_open(&rho_shares)
+    }
+
+    /// Returns [`E2fError::InvalidProduct`] if `c`, `c'` or `r^2` is not the product of the
+    /// authenticated shares.
+    pub fn preprocess6_check(
+        &mut self,
+        w_shares: Vec<AuthShare<C::Field>>,
+    ) -> Result<(), E2fError> {
+        self.sacrifice_mut().check(&w_shares)
+    }
+
+    /// Returns [`E2fError::InvalidPoint`] if `ec_point` is not on the curve.
+    pub fn handshake5_input_ec(
+        self,
+        ec_point: (C::Field, C::Field),
+    ) -> Result<Verifier<Handshake5<C>, C>, E2fError> {
+        assert!(
+            self.sacrifice().passed(),
+            "the preprocessed products have not been checked"
+        );
+
+        let mut verifier = self.try_transition(|inner| inner.handshake5_input_ec(ec_point))?;
+        verifier.t_coin_toss = Some(CoinToss::new(verifier.inner.handshake5_t_share_open()));
+
+        Ok(verifier)
+    }
+
+    /// Returns the masks of the sacrificed products and the factors they are multiplied with.
+    fn sacrifice_factors(&self) -> ([C::Field; 3], [C::Field; 3]) {
+        let masks = self.inner.state.masks;
+        (self.sacrifice_masks, [masks.b, masks.b_prime, masks.r])
+    }
+
+    /// Returns the shares of `a, b, b', c, c', r, r^2`, of the masks `f` and of the sacrificed
+    /// products `h`.
+    fn preprocess5_vole_shares(&self) -> Vec<C::Field> {
+        let state = &self.inner.state;
+        let sacrificed = self
+            .sacrificed
+            .expect("the sacrificed products should be set");
+
+        [
+            [
+                state.masks.a,
+                state.masks.b,
+                state.masks.b_prime,
+                state.c,
+                state.c_prime,
+                state.masks.r,
+                state.r_squared_share,
+            ]
+            .as_slice(),
+            &self.sacrifice_masks,
+            &sacrificed,
+        ]
+        .concat()
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Verifier<Handshake5<C>, C> {
+    pub fn handshake5_ole_input(&self, ole: &mut Ole<C::Field>) {
+        self.inner.handshake5_ole_input(ole);
+    }
+
+    pub fn handshake5_t_commit(&self) -> HashCommitment {
+        self.t_coin_toss().commitment()
+    }
+
+    pub fn handshake5_t_share_open(&self) -> Decommitment<C::Field> {
+        self.t_coin_toss().decommitment()
+    }
+
+    /// Checks the opening of the prover's share of `t`.
+    pub fn handshake5_ole_output(
+        self,
+        ole: &mut Ole<C::Field>,
+        t_commitment: HashCommitment,
+        t_decommitment: Decommitment<C::Field>,
+    ) -> Result<Verifier<Handshake5Slope<C>, C>, E2fError> {
+        let t = self
+            .t_coin_toss()
+            .open(Role::Sender, t_commitment, t_decommitment)?;
+
+        Ok(self.transition(|inner| inner.handshake5_ole_output(ole, t)))
+    }
+
+    fn t_coin_toss(&self) -> &CoinToss<C::Field> {
+        self.t_coin_toss
+            .as_ref()
+            .expect("the EC point should be set")
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Verifier<Handshake5Slope<C>, C> {
+    pub fn handshake5_sacrifice_ole_input(&self, ole: &mut Ole<C::Field>) {
+        let (f, y) = self.handshake5_sacrifice_factors();
+        ole.input(Role::Receiver, sacrifice::ole_input(Role::Receiver, f, y));
+    }
+
+    pub fn handshake5_sacrifice_ole_output(&mut self, ole: &mut Ole<C::Field>) {
+        let (f, y) = self.handshake5_sacrifice_factors();
+        self.sacrificed = Some(sacrifice::ole_output(f, y, ole.output(Role::Receiver)));
+    }
+
+    pub fn handshake5_vole_input(
+        &self,
+        vole_prover: &mut Vole<C::Field>,
+        vole_verifier: &mut Vole<C::Field>,
+    ) {
+        vole_prover.input_delta(self.delta);
+        vole_verifier.input_x(self.handshake5_vole_shares());
+    }
+
+    pub fn handshake5_vole_output(
+        &mut self,
+        vole_prover: &mut Vole<C::Field>,
+        vole_verifier: &mut Vole<C::Field>,
+    ) {
+        let keys = vole_prover.output_keys();
+        let macs = vole_verifier.output_macs();
+
+        let values = sacrifice::auth_values(&self.handshake5_vole_shares(), &macs, &keys);
+        self.sacrifice = Some(Sacrifice::new(
+            self.delta,
+            Role::Sender,
+            sacrifice::handshake_products(Role::Receiver, &values),
+        ));
+
+        let auth = |i: usize| Auth {
+            mac: macs[i],
+            key: keys[i],
+        };
+        let (x, y, x_squared, x1_x2) = (auth(0), auth(1), auth(2), auth(3));
+        let t = self.inner.state.t;
+
+        // The verifier's shares are `x2 + t * y2` and `y2 + t * (x2^2 + x1_x2_share)`, the
+        // prover's shares are `-x1 + t * y1` and `-y1 + t * (x1^2 + x1_x2_share + a)`. The prover
+        // adds the public `t * a` to its share, so the key for its share is shifted by
+        // `delta * t * a`.
+        let denominator = Auth {
+            mac: x.mac + t * y.mac,
+            key: -x.key + t * y.key,
+        };
+        let numerator = Auth {
+            mac: y.mac + t * (x_squared.mac + x1_x2.mac),
+            key: -y.key + t * (x_squared.key + x1_x2.key) + -(self.delta * t * C::a()),
+        };
+
+        self.ec_point_auth = Some((x, y));
+        self.slope_auth = Some((denominator, numerator));
+    }
+
+    pub fn handshake5_s_commit(&self) -> HashCommitment {
+        self.sacrifice().s_commitment()
+    }
+
+    pub fn handshake5_s_open(&self) -> Decommitment<C::Field> {
+        self.sacrifice().s_decommitment()
+    }
+
+    /// Checks the opening of the prover's share of the challenge and opens the shares of `rho`.
+    pub fn handshake5_rho_shares_open(
+        &mut self,
+        s_commitment: HashCommitment,
+        s_decommitment: Decommitment<C::Field>,
+    ) -> Result<Vec<AuthShare<C::Field>>, E2fError> {
+        self.sacrifice_mut()
+            .rho_shares_open(s_commitment, s_decommitment)
+    }
+
+    pub fn handshake5_w_shares_open(
+        &mut self,
+        rho_shares: Vec<AuthShare<C::Field>>,
+    ) -> Result<Vec<AuthShare<C::Field>>, E2fError> {
+        self.sacrifice_mut().w_shares_open(&rho_shares)
+    }
+
+    /// Returns [`E2fError::InvalidProduct`] if the authenticated `x1^2`, `x2^2` or `x1 * x2` is
+    /// not the product of the authenticated x-coordinates.
+    pub fn handshake5_check(&mut self, w_shares: Vec<AuthShare<C::Field>>) -> Result<(), E2fError> {
+        self.sacrifice_mut().check(&w_shares)
+    }
+
+    pub fn handshake5_varepsilon1_share_open(&self) -> AuthShare<C::Field> {
+        AuthShare {
+            share: self.inner.handshake5_varepsilon1_share_open(),
+            mac: self.varepsilon1_auth().mac,
+        }
+    }
+
+    pub fn handshake5_set_omega(
+        mut self,
+        varepsilon1_share: AuthShare<C::Field>,
+    ) -> Result<Verifier<Handshake6<C>, C>, E2fError> {
+        assert!(
+            self.sacrifice().passed(),
+            "the products of the x-coordinates have not been checked"
+        );
+        self.verify(self.varepsilon1_auth().key, varepsilon1_share)?;
+
+        let varepsilon1 = self.inner.handshake5_varepsilon1_share_open() + varepsilon1_share.share;
+
+        let auth = self.auth.unwrap();
+        self.omega_auth = Some(auth.a * varepsilon1 + auth.c);
+
+        Ok(self.transition(|inner| inner.handshake5_set_omega(varepsilon1)))
+    }
+
+    /// Returns the masks of the sacrificed products and the factors they are multiplied with. The
+    /// verifier's share of `x1` is 0.
+    fn handshake5_sacrifice_factors(&self) -> ([C::Field; 3], [C::Field; 3]) {
+        let (x2, _) = self.inner.state.ec_point;
+        (self.handshake_sacrifice_masks, [C::Field::zero(), x2, x2])
+    }
+
+    /// Returns the shares of `x`, `y`, `x^2` and `x1 * x2`, of the masks `f` and of the sacrificed
+    /// products `h`.
+    fn handshake5_vole_shares(&self) -> Vec<C::Field> {
+        let (x2, y2) = self.inner.state.ec_point;
+        let x1_x2_share = self.inner.state.x1_x2_share;
+        let sacrificed = self
+            .sacrificed
+            .expect("the sacrificed products should be set");
+
+        [
+            [x2, y2, x2 * x2, x1_x2_share].as_slice(),
+            &self.handshake_sacrifice_masks,
+            &sacrificed,
+        ]
+        .concat()
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Verifier<Handshake6<C>, C> {
+    pub fn handshake6_omega_share_open(&self) -> AuthShare<C::Field> {
+        AuthShare {
+            share: self.inner.handshake6_omega_share_open(),
+            mac: self.omega_auth.unwrap().mac,
+        }
+    }
+
+    pub fn handshake6_varepsilon2_share_open(&self) -> AuthShare<C::Field> {
+        AuthShare {
+            share: self.inner.handshake6_varepsilon2_share_open(),
+            mac: self.varepsilon2_auth().mac,
+        }
+    }
+
+    pub fn handshake6_set_eta(
+        self,
+        omega_share: AuthShare<C::Field>,
+        varepsilon2_share: AuthShare<C::Field>,
+    ) -> Result<Verifier<Handshake7<C>, C>, E2fError> {
+        self.verify(self.omega_auth.unwrap().key, omega_share)?;
+        self.verify(self.varepsilon2_auth().key, varepsilon2_share)?;
+
+        let omega = self.inner.handshake6_omega_share_open() + omega_share.share;
+        let varepsilon2 = self.inner.handshake6_varepsilon2_share_open() + varepsilon2_share.share;
+
+        let auth = self.auth.unwrap();
+        let mut next = self.try_transition(|inner| inner.handshake6_set_eta(omega, varepsilon2))?;
+        next.eta_auth = Some((auth.a * varepsilon2 + auth.c_prime) * omega.inverse());
+
+        Ok(next)
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Verifier<Handshake7<C>, C> {
+    pub fn handshake7_varepsilon3_share_open(&self) -> AuthShare<C::Field> {
+        AuthShare {
+            share: self.inner.handshake7_varepsilon3_share_open(),
+            mac: self.varepsilon3_auth().mac,
+        }
+    }
+
+    pub fn handshake7_set_z2(
+        mut self,
+        varepsilon3_share: AuthShare<C::Field>,
+    ) -> Result<Verifier<Handshake8<C>, C>, E2fError> {
+        self.verify(self.varepsilon3_auth().key, varepsilon3_share)?;
+
+        let varepsilon3 = self.inner.handshake7_varepsilon3_share_open() + varepsilon3_share.share;
+
+        // The prover adds the public varepsilon3^2 to its share, so the key for its share is
+        // shifted by delta * varepsilon3^2.
+        let two = C::Field::one() + C::Field::one();
+        let auth = self.auth.unwrap();
+        let x = self.ec_point_auth.unwrap().0;
+        let z_auth = auth.r * (two * varepsilon3) + auth.r_squared + -x;
+
+        self.z_auth = Some(Auth {
+            mac: z_auth.mac,
+            key: z_auth.key + -(self.delta * varepsilon3 * varepsilon3),
+        });
+
+        Ok(self.transition(|inner| inner.handshake7_set_z2(varepsilon3)))
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Verifier<Handshake8<C>, C> {
+    pub fn handshake8_z2_open(&self) -> AuthShare<C::Field> {
+        AuthShare {
+            share: self.inner.handshake8_z2_open(),
+            mac: self.z_auth.unwrap().mac,
+        }
+    }
+
+    /// Checks the opened share of the prover and returns the x-coordinate.
+    ///
+    /// Only needed if the output is opened, otherwise the parties keep their shares.
+    pub fn handshake8_open_z(&self, z1: AuthShare<C::Field>) -> Result<C::Field, E2fError> {
+        self.verify(self.z_auth.unwrap().key, z1)?;
+
+        Ok(z1.share + self.inner.handshake8_z2_open())
+    }
+}
+
+impl<S, C: ShortWeierstrassCurve> Verifier<S, C> {
+    fn transition<T>(self, f: impl FnOnce(e2f::Verifier<S>) -> e2f::Verifier<T>) -> Verifier<T, C> {
+        Verifier {
+            inner: f(self.inner),
+            delta: self.delta,
+            sacrifice_masks: self.sacrifice_masks,
+            handshake_sacrifice_masks: self.handshake_sacrifice_masks,
+            sacrificed: self.sacrificed,
+            auth: self.auth,
+            sacrifice: self.sacrifice,
+            t_coin_toss: self.t_coin_toss,
+            ec_point_auth: self.ec_point_auth,
+            slope_auth: self.slope_auth,
+            omega_auth: self.omega_auth,
+            eta_auth: self.eta_auth,
+            z_auth: self.z_auth,
+        }
+    }
+
+    fn try_transition<T>(
+        self,
+        f: impl FnOnce(e2f::Verifier<S>) -> Result<e2f::Verifier<T>, E2fError>,
+    ) -> Result<Verifier<T, C>, E2fError> {
+        Ok(Verifier {
+            inner: f(self.inner)?,
+            delta: self.delta,
+            sacrifice_masks: self.sacrifice_masks,
+            handshake_sacrifice_masks: self.handshake_sacrifice_masks,
+            sacrificed: self.sacrificed,
+            auth: self.auth,
+            sacrifice: self.sacrifice,
+            t_coin_toss: self.t_coin_toss,
+            ec_point_auth: self.ec_point_auth,
+            slope_auth: self.slope_auth,
+            omega_auth: self.omega_auth,
+            eta_auth: self.eta_auth,
+            z_auth: self.z_auth,
+        })
+    }
+
+    fn varepsilon1_auth(&self) -> Auth<C::Field> {
+        self.slope_auth.unwrap().0 + -self.auth.unwrap().b
+    }
+
+    fn varepsilon2_auth(&self) -> Auth<C::Field> {
+        self.slope_auth.unwrap().1 + -self.auth.unwrap().b_prime
+    }
+
+    fn varepsilon3_auth(&self) -> Auth<C::Field> {
+        self.eta_auth.unwrap() + -self.auth.unwrap().r
+    }
+
+    fn sacrifice(&self) -> &Sacrifice<C::Field> {
+        self.sacrifice.as_ref().expect("the VOLE should be run")
+    }
+
+    fn sacrifice_mut(&mut self) -> &mut Sacrifice<C::Field> {
+        self.sacrifice.as_mut().expect("the VOLE should be run")
+    }
+
+    fn verify(&self, key: C::Field, opened: AuthShare<C::Field>) -> Result<(), E2fError> {
+        verify(self.delta, key, opened, Role::Sender)
+    }
+}
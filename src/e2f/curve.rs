@@ -0,0 +1,243 @@
+//! Short Weierstrass curves, over which the E2F protocol adds points.
+//!
+//! A curve is a marker type implementing [`ShortWeierstrassCurve`], so adding a new curve only
+//! needs the field of its coordinates, its parameters and its generator.
+
+use crate::{fp384::P384, fsecp256k1::Secp256k1};
+use itybity::ToBits;
+use mpz_share_conversion_core::fields::{p256::P256, Field};
+use std::fmt::Debug;
+
+/// A curve `y^2 = x^3 + a * x + b` over the field [`ShortWeierstrassCurve::Field`].
+pub trait ShortWeierstrassCurve: Debug + 'static {
+    /// The field of the coordinates.
+    type Field: Field;
+
+    /// The curve parameter `a`.
+    fn a() -> Self::Field;
+
+    /// The curve parameter `b`.
+    fn b() -> Self::Field;
+
+    /// The generator of the group, which is used by the cryptosystems over this curve.
+    fn generator() -> (Self::Field, Self::Field);
+
+    /// Checks that `y^2 = x^3 + a * x + b`.
+    fn is_on_curve((x, y): (Self::Field, Self::Field)) -> bool {
+        y * y == x * x * x + Self::a() * x + Self::b()
+    }
+
+    /// Adds two points with the affine formulas. This is the reference for the E2F output.
+    ///
+    /// Returns `None` if the sum is the point at infinity.
+    fn add(
+        (x1, y1): (Self::Field, Self::Field),
+        (x2, y2): (Self::Field, Self::Field),
+    ) -> Option<(Self::Field, Self::Field)> {
+        let one = Self::Field::one();
+        let two = one + one;
+        let three = two + one;
+
+        let slope = if x1 != x2 {
+            (y2 + -y1) * (x2 + -x1).inverse()
+        } else if y1 == y2 && y1 != Self::Field::zero() {
+            (three * x1 * x1 + Self::a()) * (two * y1).inverse()
+        } else {
+            return None;
+        };
+
+        let x3 = slope * slope + -x1 + -x2;
+        let y3 = slope * (x1 + -x3) + -y1;
+
+        Some((x3, y3))
+    }
+}
+
+/// The NIST curve P-256, which is also known as secp256r1.
+#[derive(Debug, Clone, Copy)]
+pub struct P256Curve;
+
+impl ShortWeierstrassCurve for P256Curve {
+    type Field = P256;
+
+    fn a() -> P256 {
+        -P256::new(3).unwrap()
+    }
+
+    fn b() -> P256 {
+        from_be_bytes(&P256_B)
+    }
+
+    fn generator() -> (P256, P256) {
+        (from_be_bytes(&P256_GX), from_be_bytes(&P256_GY))
+    }
+}
+
+/// The NIST curve P-384, which is also known as secp384r1.
+#[derive(Debug, Clone, Copy)]
+pub struct P384Curve;
+
+impl ShortWeierstrassCurve for P384Curve {
+    type Field = P384;
+
+    fn a() -> P384 {
+        -P384::new(3)
+    }
+
+    fn b() -> P384 {
+        from_be_bytes(&P384_B)
+    }
+
+    fn generator() -> (P384, P384) {
+        (from_be_bytes(&P384_GX), from_be_bytes(&P384_GY))
+    }
+}
+
+/// The curve secp256k1, which is used by Bitcoin and Ethereum.
+#[derive(Debug, Clone, Copy)]
+pub struct Secp256k1Curve;
+
+impl ShortWeierstrassCurve for Secp256k1Curve {
+    type Field = Secp256k1;
+
+    fn a() -> Secp256k1 {
+        Secp256k1::zero()
+    }
+
+    fn b() -> Secp256k1 {
+        Secp256k1::new(7)
+    }
+
+    fn generator() -> (Secp256k1, Secp256k1) {
+        (from_be_bytes(&SECP256K1_GX), from_be_bytes(&SECP256K1_GY))
+    }
+}
+
+/// The curve parameter `b` of P-256 in big-endian byte order.
+const P256_B: [u8; 32] = [
+    0x5a, 0xc6, 0x35, 0xd8, 0xaa, 0x3a, 0x93, 0xe7, 0xb3, 0xeb, 0xbd, 0x55, 0x76, 0x98, 0x86, 0xbc,
+    0x65, 0x1d, 0x06, 0xb0, 0xcc, 0x53, 0xb0, 0xf6, 0x3b, 0xce, 0x3c, 0x3e, 0x27, 0xd2, 0x60, 0x4b,
+];
+
+/// The x-coordinate of the generator of P-256 in big-endian byte order.
+const P256_GX: [u8; 32] = [
+    0x6b, 0x17, 0xd1, 0xf2, 0xe1, 0x2c, 0x42, 0x47, 0xf8, 0xbc, 0xe6, 0xe5, 0x63, 0xa4, 0x40, 0xf2,
+    0x77, 0x03, 0x7d, 0x81, 0x2d, 0xeb, 0x33, 0xa0, 0xf4, 0xa1, 0x39, 0x45, 0xd8, 0x98, 0xc2, 0x96,
+];
+
+/// The y-coordinate of the generator of P-256 in big-endian byte order.
+const P256_GY: [u8; 32] = [
+    0x4f, 0xe3, 0x42, 0xe2, 0xfe, 0x1a, 0x7f, 0x9b, 0x8e, 0xe7, 0xeb, 0x4a, 0x7c, 0x0f, 0x9e, 0x16,
+    0x2b, 0xce, 0x33, 0x57, 0x6b, 0x31, 0x5e, 0xce, 0xcb, 0xb6, 0x40, 0x68, 0x37, 0xbf, 0x51, 0xf5,
+];
+
+/// The curve parameter `b` of P-384 in big-endian byte order.
+const P384_B: [u8; 48] = [
+    0xb3, 0x31, 0x2f, 0xa7, 0xe2, 0x3e, 0xe7, 0xe4, 0x98, 0x8e, 0x05, 0x6b, 0xe3, 0xf8, 0x2d, 0x19,
+    0x18, 0x1d, 0x9c, 0x6e, 0xfe, 0x81, 0x41, 0x12, 0x03, 0x14, 0x08, 0x8f, 0x50, 0x13, 0x87, 0x5a,
+    0xc6, 0x56, 0x39, 0x8d, 0x8a, 0x2e, 0xd1, 0x9d, 0x2a, 0x85, 0xc8, 0xed, 0xd3, 0xec, 0x2a, 0xef,
+];
+
+/// The x-coordinate of the generator of P-384 in big-endian byte order.
+const P384_GX: [u8; 48] = [
+    0xaa, 0x87, 0xca, 0x22, 0xbe, 0x8b, 0x05, 0x37, 0x8e, 0xb1, 0xc7, 0x1e, 0xf3, 0x20, 0xad, 0x74,
+    0x6e, 0x1d, 0x3b, 0x62, 0x8b, 0xa7, 0x9b, 0x98, 0x59, 0xf7, 0x41, 0xe0, 0x82, 0x54, 0x2a, 0x38,
+    0x55, 0x02, 0xf2, 0x5d, 0xbf, 0x55, 0x29, 0x6c, 0x3a, 0x54, 0x5e, 0x38, 0x72, 0x76, 0x0a, 0xb7,
+];
+
+/// The y-coordinate of the generator of P-384 in big-endian byte order.
+const P384_GY: [u8; 48] = [
+    0x36, 0x17, 0xde, 0x4a, 0x96, 0x26, 0x2c, 0x6f, 0x5d, 0x9e, 0x98, 0xbf, 0x92, 0x92, 0xdc, 0x29,
+    0xf8, 0xf4, 0x1d, 0xbd, 0x28, 0x9a, 0x14, 0x7c, 0xe9, 0xda, 0x31, 0x13, 0xb5, 0xf0, 0xb8, 0xc0,
+    0x0a, 0x60, 0xb1, 0xce, 0x1d, 0x7e, 0x81, 0x9d, 0x7a, 0x43, 0x1d, 0x7c, 0x90, 0xea, 0x0e, 0x5f,
+];
+
+/// The x-coordinate of the generator of secp256k1 in big-endian byte order.
+const SECP256K1_GX: [u8; 32] = [
+    0x79, 0xbe, 0x66, 0x7e, 0xf9, 0xdc, 0xbb, 0xac, 0x55, 0xa0, 0x62, 0x95, 0xce, 0x87, 0x0b, 0x07,
+    0x02, 0x9b, 0xfc, 0xdb, 0x2d, 0xce, 0x28, 0xd9, 0x59, 0xf2, 0x81, 0x5b, 0x16, 0xf8, 0x17, 0x98,
+];
+
+/// The y-coordinate of the generator of secp256k1 in big-endian byte order.
+const SECP256K1_GY: [u8; 32] = [
+    0x48, 0x3a, 0xda, 0x77, 0x26, 0xa3, 0xc4, 0x65, 0x5d, 0xa4, 0xfb, 0xfc, 0x0e, 0x11, 0x08, 0xa8,
+    0xfd, 0x17, 0xb4, 0x48, 0xa6, 0x85, 0x54, 0x19, 0x9c, 0x47, 0xd0, 0x8f, 0xfb, 0x10, 0xd4, 0xb8,
+];
+
+/// Converts a big-endian coordinate, as in SEC1 encodings, into a field element.
+///
+/// # Panics
+///
+/// Panics if the coordinate is not smaller than the modulus.
+pub(crate) fn from_be_bytes<F: Field>(bytes: &[u8]) -> F {
+    F::from_msb0_iter(bytes.iter_msb0())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::e2f::tests::sec1_to_point;
+
+    #[test]
+    fn test_p256_curve() {
+        use p256::{elliptic_curve::sec1::ToEncodedPoint, AffinePoint, ProjectivePoint};
+
+        let to_p256 = |point: AffinePoint| -> (P256, P256) {
+            sec1_to_point(point.to_encoded_point(false).as_bytes())
+        };
+
+        let generator = P256Curve::generator();
+        assert_eq!(generator, to_p256(AffinePoint::GENERATOR));
+        assert!(P256Curve::is_on_curve(generator));
+
+        let doubled = P256Curve::add(generator, generator).unwrap();
+        assert_eq!(
+            doubled,
+            to_p256((ProjectivePoint::GENERATOR + ProjectivePoint::GENERATOR).to_affine())
+        );
+
+        let tripled = P256Curve::add(doubled, generator).unwrap();
+        let expected = ProjectivePoint::GENERATOR * p256::Scalar::from(3_u64);
+        assert_eq!(tripled, to_p256(expected.to_affine()));
+
+        assert_eq!(P256Curve::add(generator, (generator.0, -generator.1)), None);
+    }
+
+    #[test]
+    fn test_p384_curve() {
+        use p384::{elliptic_curve::sec1::ToEncodedPoint, AffinePoint, ProjectivePoint};
+
+        let to_p384 = |point: AffinePoint| -> (P384, P384) {
+            sec1_to_point(point.to_encoded_point(false).as_bytes())
+        };
+
+        let generator = P384Curve::generator();
+        assert_eq!(generator, to_p384(AffinePoint::GENERATOR));
+        assert!(P384Curve::is_on_curve(generator));
+
+        let doubled = P384Curve::add(generator, generator).unwrap();
+        assert_eq!(
+            doubled,
+            to_p384((ProjectivePoint::GENERATOR + ProjectivePoint::GENERATOR).to_affine())
+        );
+    }
+
+    #[test]
+    fn test_secp256k1_curve() {
+        use k256::{elliptic_curve::sec1::ToEncodedPoint, AffinePoint, ProjectivePoint};
+
+        let to_secp256k1 = |point: AffinePoint| -> (Secp256k1, Secp256k1) {
+            sec1_to_point(point.to_encoded_point(false).as_bytes())
+        };
+
+        let generator = Secp256k1Curve::generator();
+        assert_eq!(generator, to_secp256k1(AffinePoint::GENERATOR));
+        assert!(Secp256k1Curve::is_on_curve(generator));
+
+        let doubled = Secp256k1Curve::add(generator, generator).unwrap();
+        assert_eq!(
+            doubled,
+            to_secp256k1((ProjectivePoint::GENERATOR + ProjectivePoint::GENERATOR).to_affine())
+        );
+    }
+}
@@ -1,48 +1,157 @@
 //! This module is a testing ground for the E2F protocol (page 33) from <https://eprint.iacr.org/2023/964>
-
+//!
+//! The paper computes the slope `(y2 - y1) / (x2 - x1)`, which is undefined if the two points have
+//! the same x-coordinate. Instead we compute the slope as
+//!
+//! `((y2 - y1) + t * (x1^2 + x1 * x2 + x2^2 + a)) / ((x2 - x1) + t * (y1 + y2))`
+//!
+//! for a random public `t`, which is opened after the inputs are fixed. Both summands of the
+//! numerator are the slope times the corresponding summands of the denominator if the points are
+//! different, and only the second ones are non-zero if the points are equal, so the same formula
+//! also covers the doubling case without revealing which case occurred. The denominator is 0 for
+//! every `t` if and only if the sum is the point at infinity. The cross term `x1 * x2` is computed
+//! with an additional OLE.
+//!
+//! The parties are generic over the curve, which implements [`ShortWeierstrassCurve`]. The curves
+//! P-256, P-384 and secp256k1 are in [`curve`], and the short Weierstrass form of Curve25519 is used
+//! by [`x25519`]. [`pms`] takes P-256 points as they are used in TLS.
+
+pub mod authenticated;
+pub mod curve;
+pub mod pms;
 mod prover;
+pub mod state;
 mod verifier;
+pub mod x25519;
 
-use crate::func::ole::Ole;
-use mpz_share_conversion_core::fields::p256::P256;
+use crate::func::{ole::Ole, Role};
+use curve::ShortWeierstrassCurve;
 pub use prover::Prover;
+use state::{Handshake5, Handshake8, Preprocess1, Preprocess2, Preprocessed, SumCheck4};
 pub use verifier::Verifier;
 
 /// Returns the x-coordinate shares of the sum of the two EC points
-pub fn e2f(
-    prover_point: (P256, P256),
-    prover: &mut Prover,
-    verifier_point: (P256, P256),
-    verifier: &mut Verifier,
-) -> (P256, P256) {
-    let mut ole = Ole::default();
+pub fn e2f<C: ShortWeierstrassCurve>(
+    prover_point: (C::Field, C::Field),
+    prover: Prover<Preprocess1<C>>,
+    verifier_point: (C::Field, C::Field),
+    verifier: Verifier<Preprocess1<C>>,
+) -> Result<(C::Field, C::Field), E2fError> {
+    let (prover, verifier) = preprocess(prover.preprocess1(), verifier.preprocess1());
+
+    let prover = prover.handshake5_input_ec(prover_point)?;
+    let verifier = verifier.handshake5_input_ec(verifier_point)?;
+    let (prover, verifier) = handshake(prover, verifier)?;
+
+    // Output
+    let z1 = prover.handshake8_z1_open();
+    let z2 = verifier.handshake8_z2_open();
+
+    Ok((z1, z2))
+}
+
+/// Like [`e2f`], but the parties additionally check jointly that the sum is on the curve.
+///
+/// Every party only checks that its own input is on the curve, so a malicious party could still
+/// use an invalid point. For an invalid point the formula for `x3` does not yield a point on the
+/// curve, so the parties compute shares of `y3`, open `rho * (y3^2 - (x3^3 + a * x3 + b))` for a
+/// random shared `rho` and abort with [`E2fError::SumNotOnCurve`] if it is not 0. The random `rho`
+/// hides the value of the curve equation from the other party.
+///
+/// The parties commit to their shares of `w = rho * v` before opening them, so a rushing party
+/// cannot choose its share after seeing the other one to force `w = 0`. The inputs of the OLEs
+/// computing `w` are still not authenticated, so a party which uses different shares in these OLEs
+/// is not caught by the commitment.
+pub fn e2f_with_sum_check<C: ShortWeierstrassCurve>(
+    prover_point: (C::Field, C::Field),
+    prover: Prover<Preprocess1<C>>,
+    verifier_point: (C::Field, C::Field),
+    verifier: Verifier<Preprocess1<C>>,
+) -> Result<(C::Field, C::Field), E2fError> {
+    let (prover, verifier) = preprocess(prover.preprocess1(), verifier.preprocess1());
+
+    let prover = prover.handshake5_input_ec(prover_point)?;
+    let verifier = verifier.handshake5_input_ec(verifier_point)?;
+    let (prover, verifier) = handshake(prover, verifier)?;
+
+    sum_check(prover, verifier)
+}
+
+/// Like [`e2f`], but re-runs the protocol with fresh parties up to `retries` times if omega is 0.
+///
+/// Omega is `a` times the denominator of the slope, so it is 0 if the random `a` is 0, if the
+/// random `t` cancels the denominator or if the sum is the point at infinity. Fresh randomness
+/// causes this again only with negligible probability, so if omega is still 0 after all retries,
+/// the inputs are to blame and [`E2fError::PointAtInfinity`] is returned. Without retries the
+/// error stays [`E2fError::OmegaZero`].
+pub fn e2f_with_retry<C: ShortWeierstrassCurve>(
+    prover_point: (C::Field, C::Field),
+    prover: Prover<Preprocess1<C>>,
+    verifier_point: (C::Field, C::Field),
+    verifier: Verifier<Preprocess1<C>>,
+    retries: usize,
+) -> Result<(C::Field, C::Field), E2fError> {
+    let mut result = e2f(prover_point, prover, verifier_point, verifier);
+
+    for _ in 0..retries {
+        if result != Err(E2fError::OmegaZero) {
+            return result;
+        }
+        result = e2f::<C>(prover_point, Prover::new(), verifier_point, Verifier::new());
+    }
 
-    // Preprocessing
-    prover.preprocess1();
-    verifier.preprocess1();
+    match result {
+        Err(E2fError::OmegaZero) if retries > 0 => Err(E2fError::PointAtInfinity),
+        result => result,
+    }
+}
+
+/// The prover and the verifier in the same state.
+type Parties<S> = (Prover<S>, Verifier<S>);
+
+/// Runs the preprocessing with parties which have already sampled their masks.
+fn preprocess<C: ShortWeierstrassCurve>(
+    prover: Prover<Preprocess2<C>>,
+    verifier: Verifier<Preprocess2<C>>,
+) -> Parties<Preprocessed<C>> {
+    let mut ole = Ole::default();
 
     prover.preprocess2_ole_input(&mut ole);
     verifier.preprocess2_ole_input(&mut ole);
 
-    prover.preprocess2_ole_output(&mut ole);
-    verifier.preprocess2_ole_output(&mut ole);
+    let prover = prover.preprocess2_ole_output(&mut ole);
+    let verifier = verifier.preprocess2_ole_output(&mut ole);
+
+    let prover = prover.preprocess3();
+    let verifier = verifier.preprocess3();
 
-    prover.preprocess3();
-    verifier.preprocess3();
+    let prover = prover.preprocess4();
+    let verifier = verifier.preprocess4();
 
-    prover.preprocess4();
-    verifier.preprocess4();
+    (prover, verifier)
+}
+
+/// Runs the handshake with parties which have already input their EC points.
+fn handshake<C: ShortWeierstrassCurve>(
+    prover: Prover<Handshake5<C>>,
+    verifier: Verifier<Handshake5<C>>,
+) -> Result<Parties<Handshake8<C>>, E2fError> {
+    let mut ole = Ole::default();
+
+    prover.handshake5_ole_input(&mut ole);
+    verifier.handshake5_ole_input(&mut ole);
+
+    let t = prover.handshake5_t_share_open() + verifier.handshake5_t_share_open();
 
-    // Handshake
-    prover.handshake5_input_ec(prover_point);
-    verifier.handshake5_input_ec(verifier_point);
+    let prover = prover.handshake5_ole_output(&mut ole, t);
+    let verifier = verifier.handshake5_ole_output(&mut ole, t);
 
     let varespilon1_share_prover = prover.handshake5_varepsilon1_share_open();
     let varespilon1_share_verifier = verifier.handshake5_varepsilon1_share_open();
     let varepsilon1 = varespilon1_share_prover + varespilon1_share_verifier;
 
-    prover.handshake5_set_omega(varepsilon1);
-    verifier.handshake5_set_omega(varepsilon1);
+    let prover = prover.handshake5_set_omega(varepsilon1);
+    let verifier = verifier.handshake5_set_omega(varepsilon1);
 
     let omega_share_prover = prover.handshake6_omega_share_open();
     let omega_share_verifier = verifier.handshake6_omega_share_open();
@@ -52,28 +161,115 @@ pub fn e2f(
     let varespilon2_share_verifier = verifier.handshake6_varepsilon2_share_open();
     let varepsilon2 = varespilon2_share_prover + varespilon2_share_verifier;
 
-    prover.handshake6_set_eta(omega, varepsilon2);
-    verifier.handshake6_set_eta(omega, varepsilon2);
+    let prover = prover.handshake6_set_eta(omega, varepsilon2)?;
+    let verifier = verifier.handshake6_set_eta(omega, varepsilon2)?;
 
     let varepsilon3_share_prover = prover.handshake7_varepsilon3_share_open();
     let varepsilon3_share_verifier = verifier.handshake7_varepsilon3_share_open();
     let varepsilon3 = varepsilon3_share_prover + varepsilon3_share_verifier;
 
-    prover.handshake7_set_z1(varepsilon3);
-    verifier.handshake7_set_z2(varepsilon3);
+    let prover = prover.handshake7_set_z1(varepsilon3);
+    let verifier = verifier.handshake7_set_z2(varepsilon3);
 
-    // Output
-    let z1 = prover.handshake8_z1_open();
-    let z2 = verifier.handshake8_z2_open();
+    Ok((prover, verifier))
+}
+
+/// Checks that the sum is on the curve and returns the x-coordinate shares.
+fn sum_check<C: ShortWeierstrassCurve>(
+    prover: Prover<Handshake8<C>>,
+    verifier: Verifier<Handshake8<C>>,
+) -> Result<(C::Field, C::Field), E2fError> {
+    let (prover, verifier) = sum_check_ole(prover, verifier);
+
+    // Both parties commit to their share of `w` before opening it
+    let w_commitment_prover = prover.sum_check4_w_commit();
+    let w_commitment_verifier = verifier.sum_check4_w_commit();
+
+    let w_decommitment_prover = prover.sum_check4_w_share_open();
+    let w_decommitment_verifier = verifier.sum_check4_w_share_open();
+
+    let z1 = prover.sum_check4_output(w_commitment_verifier, w_decommitment_verifier)?;
+    let z2 = verifier.sum_check4_output(w_commitment_prover, w_decommitment_prover)?;
+
+    Ok((z1, z2))
+}
+
+/// Runs the OLEs computing the shares of `w`.
+fn sum_check_ole<C: ShortWeierstrassCurve>(
+    prover: Prover<Handshake8<C>>,
+    verifier: Verifier<Handshake8<C>>,
+) -> Parties<SumCheck4<C>> {
+    let mut ole = Ole::default();
+
+    prover.sum_check1_ole_input(&mut ole);
+    verifier.sum_check1_ole_input(&mut ole);
 
-    (z1, z2)
+    let prover = prover.sum_check1_ole_output(&mut ole);
+    let verifier = verifier.sum_check1_ole_output(&mut ole);
+
+    prover.sum_check2_ole_input(&mut ole);
+    verifier.sum_check2_ole_input(&mut ole);
+
+    let prover = prover.sum_check2_ole_output(&mut ole);
+    let verifier = verifier.sum_check2_ole_output(&mut ole);
+
+    prover.sum_check3_ole_input(&mut ole);
+    verifier.sum_check3_ole_input(&mut ole);
+
+    let prover = prover.sum_check3_ole_output(&mut ole);
+    let verifier = verifier.sum_check3_ole_output(&mut ole);
+
+    (prover, verifier)
+}
+
+/// Errors which abort the E2F protocol.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum E2fError {
+    /// An opened share of this role does not match its MAC.
+    InvalidMac(Role),
+    /// Omega is 0, so it cannot be inverted. This happens if the random `a` is 0, if the random
+    /// `t` cancels the denominator of the slope or if the sum is the point at infinity.
+    OmegaZero,
+    /// Omega is 0 even with fresh randomness, so the sum of the two points is the point at
+    /// infinity, which has no x-coordinate.
+    PointAtInfinity,
+    /// The input point of a party is not on the curve.
+    InvalidPoint,
+    /// The joint check found that the sum of the two points is not on the curve.
+    SumNotOnCurve,
+    /// The opening of a commitment of this role does not match the commitment.
+    InvalidOpening(Role),
+    /// An authenticated share of a product is not the product of the authenticated shares of its
+    /// factors, so a party has changed its share of the product.
+    InvalidProduct,
+}
+
+impl std::fmt::Display for E2fError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            E2fError::InvalidMac(role) => write!(f, "invalid MAC on a share from {role:?}"),
+            E2fError::OmegaZero => write!(f, "omega is 0"),
+            E2fError::PointAtInfinity => write!(f, "the sum is the point at infinity"),
+            E2fError::InvalidPoint => write!(f, "the input point is not on the curve"),
+            E2fError::SumNotOnCurve => write!(f, "the sum is not on the curve"),
+            E2fError::InvalidOpening(role) => {
+                write!(f, "invalid opening of a commitment from {role:?}")
+            }
+            E2fError::InvalidProduct => write!(f, "an authenticated product is wrong"),
+        }
+    }
 }
 
+impl std::error::Error for E2fError {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use mpz_share_conversion_core::Field;
+    use crate::{fp384::P384, fsecp256k1::Secp256k1};
+    use curve::{P256Curve, P384Curve, Secp256k1Curve};
+    use mpz_share_conversion_core::{fields::p256::P256, Field};
     use p256::{elliptic_curve::sec1::ToEncodedPoint, EncodedPoint, NonZeroScalar, PublicKey};
+    use pms::point_to_p256;
     use rand::thread_rng;
 
     #[test]
@@ -82,100 +278,350 @@ mod tests {
         let prover_scalar = NonZeroScalar::random(&mut rng);
         let verifier_scalar = NonZeroScalar::random(&mut rng);
 
-        let prover_ec = point_to_p256(scalar_to_encoded_point(prover_scalar));
-        let mut prover = Prover::default();
+        let prover_ec = point_to_p256(scalar_to_encoded_point(prover_scalar)).unwrap();
+        let prover = Prover::new();
 
-        let verifier_ec = point_to_p256(scalar_to_encoded_point(verifier_scalar));
-        let mut verifier = Verifier::default();
+        let verifier_ec = point_to_p256(scalar_to_encoded_point(verifier_scalar)).unwrap();
+        let verifier = Verifier::new();
 
-        let (z1, z2) = e2f(prover_ec, &mut prover, verifier_ec, &mut verifier);
+        let (z1, z2) = e2f::<P256Curve>(prover_ec, prover, verifier_ec, verifier).unwrap();
 
-        let x_ec_expected = add_ec_points(prover_ec, verifier_ec);
+        let x_ec_expected = P256Curve::add(prover_ec, verifier_ec).unwrap();
         assert_eq!(z1 + z2, x_ec_expected.0);
     }
 
+    #[test]
+    fn test_e2f_p384() {
+        use p384::{elliptic_curve::sec1::ToEncodedPoint, NonZeroScalar, PublicKey};
+
+        let mut rng = thread_rng();
+        let prover_pk = PublicKey::from_secret_scalar(&NonZeroScalar::random(&mut rng));
+        let verifier_pk = PublicKey::from_secret_scalar(&NonZeroScalar::random(&mut rng));
+
+        let prover_ec: (P384, P384) = sec1_to_point(prover_pk.to_encoded_point(false).as_bytes());
+        let verifier_ec = sec1_to_point(verifier_pk.to_encoded_point(false).as_bytes());
+        assert!(P384Curve::is_on_curve(prover_ec));
+
+        let sum = (prover_pk.to_projective() + verifier_pk.to_projective()).to_affine();
+        let x_ec_expected: P384 = sec1_to_point(sum.to_encoded_point(false).as_bytes()).0;
+
+        let (z1, z2) =
+            e2f::<P384Curve>(prover_ec, Prover::new(), verifier_ec, Verifier::new()).unwrap();
+        assert_eq!(z1 + z2, x_ec_expected);
+
+        let (z1, z2) =
+            e2f_with_sum_check::<P384Curve>(prover_ec, Prover::new(), verifier_ec, Verifier::new())
+                .unwrap();
+        assert_eq!(z1 + z2, x_ec_expected);
+    }
+
+    #[test]
+    fn test_e2f_secp256k1() {
+        use k256::{elliptic_curve::sec1::ToEncodedPoint, NonZeroScalar, PublicKey};
+
+        let mut rng = thread_rng();
+        let prover_pk = PublicKey::from_secret_scalar(&NonZeroScalar::random(&mut rng));
+        let verifier_pk = PublicKey::from_secret_scalar(&NonZeroScalar::random(&mut rng));
+
+        let prover_ec: (Secp256k1, Secp256k1) =
+            sec1_to_point(prover_pk.to_encoded_point(false).as_bytes());
+        let verifier_ec = sec1_to_point(verifier_pk.to_encoded_point(false).as_bytes());
+        assert!(Secp256k1Curve::is_on_curve(prover_ec));
+
+        let sum = (prover_pk.to_projective() + verifier_pk.to_projective()).to_affine();
+        let x_ec_expected: Secp256k1 = sec1_to_point(sum.to_encoded_point(false).as_bytes()).0;
+
+        let (z1, z2) =
+            e2f::<Secp256k1Curve>(prover_ec, Prover::new(), verifier_ec, Verifier::new()).unwrap();
+        assert_eq!(z1 + z2, x_ec_expected);
+
+        let (z1, z2) = e2f_with_sum_check::<Secp256k1Curve>(
+            prover_ec,
+            Prover::new(),
+            verifier_ec,
+            Verifier::new(),
+        )
+        .unwrap();
+        assert_eq!(z1 + z2, x_ec_expected);
+
+        // Doubling
+        let doubled = prover_pk.to_projective().double().to_affine();
+        let (z1, z2) =
+            e2f::<Secp256k1Curve>(prover_ec, Prover::new(), prover_ec, Verifier::new()).unwrap();
+        assert_eq!(
+            z1 + z2,
+            sec1_to_point(doubled.to_encoded_point(false).as_bytes()).0
+        );
+    }
+
     #[test]
     fn test_sharing_sums() {
         let mut rng = thread_rng();
         let prover_scalar = NonZeroScalar::random(&mut rng);
         let verifier_scalar = NonZeroScalar::random(&mut rng);
 
-        let prover_ec = point_to_p256(scalar_to_encoded_point(prover_scalar));
-        let mut prover = Prover::default();
+        let prover_ec = point_to_p256(scalar_to_encoded_point(prover_scalar)).unwrap();
+        let verifier_ec = point_to_p256(scalar_to_encoded_point(verifier_scalar)).unwrap();
 
-        let verifier_ec = point_to_p256(scalar_to_encoded_point(verifier_scalar));
-        let mut verifier = Verifier::default();
+        let two = P256::new(2).unwrap();
+        let mut ole = Ole::default();
 
-        let _ = e2f(prover_ec, &mut prover, verifier_ec, &mut verifier);
+        // Preprocessing
+        let prover = Prover::<Preprocess1>::new().preprocess1();
+        let verifier = Verifier::<Preprocess1>::new().preprocess1();
 
-        // Assertions
+        let masks1 = prover.state.masks;
+        let masks2 = verifier.state.masks;
+        let a = masks1.a + masks2.a;
+        let b = masks1.b + masks2.b;
+        let b_prime = masks1.b_prime + masks2.b_prime;
+        let r = masks1.r + masks2.r;
 
-        // c
-        let c = prover.a1.unwrap() * prover.b1.unwrap()
-            + prover.a1_b2_share.unwrap()
-            + prover.a2_b1_share.unwrap()
-            + verifier.a2_b1_share.unwrap()
-            + verifier.a1_b2_share.unwrap()
-            + verifier.a2.unwrap() * verifier.b2.unwrap();
-        assert_eq!(prover.c1.unwrap() + verifier.c2.unwrap(), c);
+        prover.preprocess2_ole_input(&mut ole);
+        verifier.preprocess2_ole_input(&mut ole);
+        let prover = prover.preprocess2_ole_output(&mut ole).preprocess3();
+        let verifier = verifier.preprocess2_ole_output(&mut ole).preprocess3();
 
-        // c_prime
-        let c_prime = prover.a1.unwrap() * prover.b1_prime.unwrap()
-            + prover.a1_b2_prime_share.unwrap()
-            + prover.a2_b1_prime_share.unwrap()
-            + verifier.a2_b1_prime_share.unwrap()
-            + verifier.a1_b2_prime_share.unwrap()
-            + verifier.a2.unwrap() * verifier.b2_prime.unwrap();
-        assert_eq!(
-            prover.c1_prime.unwrap() + verifier.c2_prime.unwrap(),
-            c_prime
-        );
+        // c and c_prime
+        assert_eq!(prover.state.c + verifier.state.c, a * b);
+        assert_eq!(prover.state.c_prime + verifier.state.c_prime, a * b_prime);
+
+        let prover = prover.preprocess4();
+        let verifier = verifier.preprocess4();
 
         // r_squared
-        let r_squared = prover.r1.unwrap() * prover.r1.unwrap()
-            + P256::new(2).unwrap() * (prover.r1_r2_share.unwrap() + verifier.r1_r2_share.unwrap())
-            + verifier.r2.unwrap() * verifier.r2.unwrap();
         assert_eq!(
-            prover.r_squared_share.unwrap() + verifier.r_squared_share.unwrap(),
-            r_squared
+            prover.state.r_squared_share + verifier.state.r_squared_share,
+            r * r
         );
+        let c = prover.state.c + verifier.state.c;
+        let c_prime = prover.state.c_prime + verifier.state.c_prime;
 
-        // omega
-        let b = prover.b1.unwrap() + verifier.b2.unwrap();
-        let varepsilon1 = -prover.ec_point.unwrap().0 + verifier.ec_point.unwrap().0 + -b;
-        let omega = varepsilon1 * (prover.a1.unwrap() + verifier.a2.unwrap())
-            + prover.c1.unwrap()
-            + verifier.c2.unwrap();
+        // Handshake
+        let prover = prover.handshake5_input_ec(prover_ec).unwrap();
+        let verifier = verifier.handshake5_input_ec(verifier_ec).unwrap();
+
+        prover.handshake5_ole_input(&mut ole);
+        verifier.handshake5_ole_input(&mut ole);
+        let t = prover.handshake5_t_share_open() + verifier.handshake5_t_share_open();
+        let prover = prover.handshake5_ole_output(&mut ole, t);
+        let verifier = verifier.handshake5_ole_output(&mut ole, t);
+
+        // x1 * x2
         assert_eq!(
-            prover.omega_share.unwrap() + verifier.omega_share.unwrap(),
-            omega
+            prover.state.x1_x2_share + verifier.state.x1_x2_share,
+            prover_ec.0 * verifier_ec.0
         );
 
+        let (x1, y1) = prover_ec;
+        let (x2, y2) = verifier_ec;
+        let curve_a = -P256::new(3).unwrap();
+        let denominator = x2 + -x1 + t * (y1 + y2);
+        let numerator = y2 + -y1 + t * (x1 * x1 + x1 * x2 + x2 * x2 + curve_a);
+
+        let varepsilon1 = prover.handshake5_varepsilon1_share_open()
+            + verifier.handshake5_varepsilon1_share_open();
+        assert_eq!(varepsilon1, denominator + -b);
+
+        let prover = prover.handshake5_set_omega(varepsilon1);
+        let verifier = verifier.handshake5_set_omega(varepsilon1);
+
+        // omega
+        let omega = prover.handshake6_omega_share_open() + verifier.handshake6_omega_share_open();
+        assert_eq!(omega, varepsilon1 * a + c);
+
+        let varepsilon2 = prover.handshake6_varepsilon2_share_open()
+            + verifier.handshake6_varepsilon2_share_open();
+        assert_eq!(varepsilon2, numerator + -b_prime);
+
+        let prover = prover.handshake6_set_eta(omega, varepsilon2).unwrap();
+        let verifier = verifier.handshake6_set_eta(omega, varepsilon2).unwrap();
+
         // eta
-        let b_prime = prover.b1_prime.unwrap() + verifier.b2_prime.unwrap();
-        let varepsilon2 = -prover.ec_point.unwrap().1 + verifier.ec_point.unwrap().1 + -b_prime;
-        let eta = (prover.omega_share.unwrap() + verifier.omega_share.unwrap()).inverse()
-            * (varepsilon2 * (prover.a1.unwrap() + verifier.a2.unwrap())
-                + prover.c1_prime.unwrap()
-                + verifier.c2_prime.unwrap());
-        assert_eq!(prover.eta_share.unwrap() + verifier.eta_share.unwrap(), eta);
+        let eta = prover.state.eta_share + verifier.state.eta_share;
+        assert_eq!(eta, omega.inverse() * (varepsilon2 * a + c_prime));
+        assert_eq!(eta, (y2 + -y1) * (x2 + -x1).inverse());
+
+        let varepsilon3 = prover.handshake7_varepsilon3_share_open()
+            + verifier.handshake7_varepsilon3_share_open();
+        assert_eq!(varepsilon3, eta + -r);
+
+        let prover = prover.handshake7_set_z1(varepsilon3);
+        let verifier = verifier.handshake7_set_z2(varepsilon3);
 
         // z
-        let varepsilon3 = prover.eta_share.unwrap()
-            + verifier.eta_share.unwrap()
-            + -prover.r1.unwrap()
-            + -verifier.r2.unwrap();
         let z = varepsilon3 * varepsilon3
-            + P256::new(2).unwrap() * varepsilon3 * (prover.r1.unwrap() + verifier.r2.unwrap())
-            + prover.r_squared_share.unwrap()
-            + verifier.r_squared_share.unwrap()
-            + -prover.ec_point.unwrap().0
-            + -verifier.ec_point.unwrap().0;
-        assert_eq!(prover.z1.unwrap() + verifier.z2.unwrap(), z);
+            + two * varepsilon3 * r
+            + r * r
+            + -prover_ec.0
+            + -verifier_ec.0;
+        assert_eq!(
+            prover.handshake8_z1_open() + verifier.handshake8_z2_open(),
+            z
+        );
+    }
+
+    #[test]
+    fn test_e2f_omega_zero() {
+        let (prover_ec, verifier_ec) = random_points();
+
+        // Force a = a1 + a2 = 0
+        let prover = Prover::<Preprocess1>::new().preprocess1();
+        let mut verifier = Verifier::<Preprocess1>::new().preprocess1();
+        verifier.state.masks.a = -prover.state.masks.a;
+
+        let (prover, verifier) = preprocess(prover, verifier);
+        let prover = prover.handshake5_input_ec(prover_ec).unwrap();
+        let verifier = verifier.handshake5_input_ec(verifier_ec).unwrap();
+
+        assert_eq!(handshake(prover, verifier).err(), Some(E2fError::OmegaZero));
+    }
+
+    #[test]
+    fn test_is_on_curve() {
+        let (ec_point, _) = random_points();
+        assert!(P256Curve::is_on_curve(ec_point));
+
+        let off_curve = (ec_point.0, ec_point.1 + P256::one());
+        assert!(!P256Curve::is_on_curve(off_curve));
+    }
+
+    #[test]
+    fn test_e2f_invalid_point() {
+        let (prover_ec, verifier_ec) = random_points();
+        let off_curve = (prover_ec.0, prover_ec.1 + P256::one());
+
+        assert_eq!(
+            e2f::<P256Curve>(off_curve, Prover::new(), verifier_ec, Verifier::new()),
+            Err(E2fError::InvalidPoint)
+        );
+        assert_eq!(
+            e2f::<P256Curve>(prover_ec, Prover::new(), off_curve, Verifier::new()),
+            Err(E2fError::InvalidPoint)
+        );
+    }
+
+    #[test]
+    fn test_e2f_with_sum_check() {
+        let (prover_ec, verifier_ec) = random_points();
+
+        let (z1, z2) =
+            e2f_with_sum_check::<P256Curve>(prover_ec, Prover::new(), verifier_ec, Verifier::new())
+                .unwrap();
+        assert_eq!(z1 + z2, P256Curve::add(prover_ec, verifier_ec).unwrap().0);
+
+        // Doubling
+        let (z1, z2) =
+            e2f_with_sum_check::<P256Curve>(prover_ec, Prover::new(), prover_ec, Verifier::new())
+                .unwrap();
+        assert_eq!(z1 + z2, P256Curve::add(prover_ec, prover_ec).unwrap().0);
+    }
+
+    #[test]
+    fn test_e2f_sum_check_invalid_point() {
+        let (prover_ec, verifier_ec) = random_points();
+        let off_curve = (verifier_ec.0, verifier_ec.1 + P256::one());
+
+        let (prover, verifier) = preprocess(
+            Prover::<Preprocess1>::new().preprocess1(),
+            Verifier::new().preprocess1(),
+        );
+        let prover = prover.handshake5_input_ec(prover_ec).unwrap();
+
+        // A malicious verifier skips the validation of its own input.
+        let verifier = Verifier {
+            state: Handshake5 {
+                preprocessed: verifier.state,
+                ec_point: off_curve,
+            },
+        };
+
+        let (prover, verifier) = handshake(prover, verifier).unwrap();
+        assert_eq!(sum_check(prover, verifier), Err(E2fError::SumNotOnCurve));
+    }
+
+    #[test]
+    fn test_e2f_sum_check_rushing_verifier() {
+        let (prover_ec, verifier_ec) = random_points();
+        let off_curve = (verifier_ec.0, verifier_ec.1 + P256::one());
+
+        let (prover, verifier) = preprocess(
+            Prover::<Preprocess1>::new().preprocess1(),
+            Verifier::new().preprocess1(),
+        );
+        let prover = prover.handshake5_input_ec(prover_ec).unwrap();
+        let verifier = Verifier {
+            state: Handshake5 {
+                preprocessed: verifier.state,
+                ec_point: off_curve,
+            },
+        };
+
+        let (prover, verifier) = handshake(prover, verifier).unwrap();
+        let (prover, verifier) = sum_check_ole(prover, verifier);
+
+        // The verifier waits for the opening of the prover and opens the share which makes `w` 0,
+        // which does not match its commitment.
+        let w_commitment_verifier = verifier.sum_check4_w_commit();
+        let mut w_decommitment_verifier = verifier.sum_check4_w_share_open();
+        w_decommitment_verifier.value = -prover.sum_check4_w_share_open().value;
+
+        assert_eq!(
+            prover.sum_check4_output(w_commitment_verifier, w_decommitment_verifier),
+            Err(E2fError::InvalidOpening(Role::Receiver))
+        );
+    }
+
+    #[test]
+    fn test_e2f_with_retry() {
+        let (prover_ec, verifier_ec) = random_points();
+
+        let (z1, z2) =
+            e2f_with_retry::<P256Curve>(prover_ec, Prover::new(), verifier_ec, Verifier::new(), 1)
+                .unwrap();
+        assert_eq!(z1 + z2, P256Curve::add(prover_ec, verifier_ec).unwrap().0);
+    }
+
+    #[test]
+    fn test_e2f_doubling() {
+        let scalar = NonZeroScalar::random(&mut thread_rng());
+        let doubled = NonZeroScalar::new(*scalar + *scalar).unwrap();
+
+        let ec_point = point_to_p256(scalar_to_encoded_point(scalar)).unwrap();
+        let ec_doubled = point_to_p256(scalar_to_encoded_point(doubled)).unwrap();
+
+        assert_eq!(P256Curve::add(ec_point, ec_point).unwrap(), ec_doubled);
+
+        let (z1, z2) =
+            e2f::<P256Curve>(ec_point, Prover::new(), ec_point, Verifier::new()).unwrap();
+        assert_eq!(z1 + z2, ec_doubled.0);
+    }
+
+    #[test]
+    fn test_e2f_point_at_infinity() {
+        let scalar = NonZeroScalar::random(&mut thread_rng());
+        let negated = -scalar;
+
+        let prover_ec = point_to_p256(scalar_to_encoded_point(scalar)).unwrap();
+        let verifier_ec = point_to_p256(scalar_to_encoded_point(negated)).unwrap();
+        assert_eq!(prover_ec.0, verifier_ec.0);
+
+        assert_eq!(
+            e2f::<P256Curve>(prover_ec, Prover::new(), verifier_ec, Verifier::new()),
+            Err(E2fError::OmegaZero)
+        );
+        assert_eq!(
+            e2f_with_retry::<P256Curve>(prover_ec, Prover::new(), verifier_ec, Verifier::new(), 0),
+            Err(E2fError::OmegaZero)
+        );
+        assert_eq!(
+            e2f_with_retry::<P256Curve>(prover_ec, Prover::new(), verifier_ec, Verifier::new(), 2),
+            Err(E2fError::PointAtInfinity)
+        );
     }
 
     #[test]
-    fn test_add_ec_points() {
+    fn test_p256_curve_add() {
         let mut rng = thread_rng();
         let scalar1 = p256::NonZeroScalar::random(&mut rng);
         let scalar2 = p256::NonZeroScalar::random(&mut rng);
@@ -185,43 +631,37 @@ mod tests {
         let pr1 = pk1.to_projective();
         let pr2 = pk2.to_projective();
 
-        let ec_added_expected = point_to_p256((pr1 + pr2).to_affine().to_encoded_point(false));
+        let ec_added_expected =
+            point_to_p256((pr1 + pr2).to_affine().to_encoded_point(false)).unwrap();
 
         let ec1 = pr1.to_affine().to_encoded_point(false);
         let ec2 = pr2.to_affine().to_encoded_point(false);
-        let ec_added = add_ec_points(point_to_p256(ec1), point_to_p256(ec2));
+        let ec_added =
+            P256Curve::add(point_to_p256(ec1).unwrap(), point_to_p256(ec2).unwrap()).unwrap();
 
         assert_eq!(ec_added, ec_added_expected);
     }
 
-    fn add_ec_points((x1, y1): (P256, P256), (x2, y2): (P256, P256)) -> (P256, P256) {
-        let nominator = y2 + -y1;
-        let denominator = x2 + -x1;
+    pub(super) fn random_points() -> ((P256, P256), (P256, P256)) {
+        let mut rng = thread_rng();
+        let prover_scalar = NonZeroScalar::random(&mut rng);
+        let verifier_scalar = NonZeroScalar::random(&mut rng);
 
-        let fraction = nominator * denominator.inverse();
-        let squared = fraction * fraction;
+        (
+            point_to_p256(scalar_to_encoded_point(prover_scalar)).unwrap(),
+            point_to_p256(scalar_to_encoded_point(verifier_scalar)).unwrap(),
+        )
+    }
 
-        let x_r = squared + -x1 + -x2;
-        let y_r = fraction * (x1 + -x_r) + -y1;
+    /// Returns the coordinates of an uncompressed SEC1 point, which is `0x04 || x || y`.
+    pub(super) fn sec1_to_point<F: Field>(point: &[u8]) -> (F, F) {
+        assert_eq!(point[0], 0x04, "the point is not uncompressed");
+        let (x, y) = point[1..].split_at(point.len() / 2);
 
-        (x_r, y_r)
+        (curve::from_be_bytes(x), curve::from_be_bytes(y))
     }
 
-    fn scalar_to_encoded_point(scalar: NonZeroScalar) -> EncodedPoint {
+    pub(super) fn scalar_to_encoded_point(scalar: NonZeroScalar) -> EncodedPoint {
         PublicKey::from_secret_scalar(&scalar).to_encoded_point(false)
     }
-
-    fn point_to_p256(point: EncodedPoint) -> (P256, P256) {
-        let mut x: [u8; 32] = (*point.x().unwrap()).into();
-        let mut y: [u8; 32] = (*point.y().unwrap()).into();
-
-        // reverse to little endian
-        x.reverse();
-        y.reverse();
-
-        let x = P256::try_from(x).unwrap();
-        let y = P256::try_from(y).unwrap();
-
-        (x, y)
-    }
 }
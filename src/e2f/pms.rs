@@ -0,0 +1,115 @@
+//! Converts the sum of two P-256 points into additive shares of the TLS pre-master secret.
+//!
+//! In ECDHE the pre-master secret is the x-coordinate of the shared point, encoded as 32 bytes in
+//! big-endian order. The points can be given as [`AffinePoint`], [`PublicKey`] or [`EncodedPoint`]
+//! in compressed or uncompressed form, so callers do not need to convert them into field elements
+//! themselves.
+//!
+//! [`PublicKey`]: p256::PublicKey
+
+use super::{
+    curve::{from_be_bytes, P256Curve},
+    e2f,
+    state::Preprocess1,
+    E2fError, Prover, Verifier,
+};
+use mpz_share_conversion_core::fields::{p256::P256, Field};
+use p256::{
+    elliptic_curve::sec1::{FromEncodedPoint, ToEncodedPoint},
+    AffinePoint, EncodedPoint,
+};
+
+/// Returns the shares of the x-coordinate of the sum of the two points.
+///
+/// The shares add up to the pre-master secret, which is encoded with [`pms_bytes`].
+pub fn pms(
+    prover_point: impl Into<EncodedPoint>,
+    prover: Prover<Preprocess1<P256Curve>>,
+    verifier_point: impl Into<EncodedPoint>,
+    verifier: Verifier<Preprocess1<P256Curve>>,
+) -> Result<(P256, P256), E2fError> {
+    e2f(
+        point_to_p256(prover_point)?,
+        prover,
+        point_to_p256(verifier_point)?,
+        verifier,
+    )
+}
+
+/// Returns the affine coordinates of a point.
+///
+/// Returns [`E2fError::InvalidPoint`] if the encoding is not a point on the curve or if it is the
+/// point at infinity.
+pub fn point_to_p256(point: impl Into<EncodedPoint>) -> Result<(P256, P256), E2fError> {
+    let point = AffinePoint::from_encoded_point(&point.into());
+    let point = Option::<AffinePoint>::from(point)
+        .ok_or(E2fError::InvalidPoint)?
+        .to_encoded_point(false);
+
+    match (point.x(), point.y()) {
+        (Some(x), Some(y)) => Ok((from_be_bytes(x), from_be_bytes(y))),
+        _ => Err(E2fError::InvalidPoint),
+    }
+}
+
+/// Encodes the x-coordinate as the pre-master secret.
+pub fn pms_bytes(x: P256) -> [u8; 32] {
+    x.to_be_bytes()
+        .try_into()
+        .expect("P256 elements have 32 bytes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p256::{ecdh::diffie_hellman, NonZeroScalar, ProjectivePoint, PublicKey};
+    use rand::thread_rng;
+
+    #[test]
+    fn test_pms() {
+        let mut rng = thread_rng();
+
+        // The client secret is shared between the prover and the verifier, so each of them
+        // multiplies the public key of the server with its share.
+        let server_secret = NonZeroScalar::random(&mut rng);
+        let server_public_key = PublicKey::from_secret_scalar(&server_secret);
+        let prover_secret = NonZeroScalar::random(&mut rng);
+        let verifier_secret = NonZeroScalar::random(&mut rng);
+
+        let server_point = server_public_key.to_projective();
+        let prover_point = (server_point * *prover_secret).to_affine();
+        let verifier_point = (server_point * *verifier_secret).to_affine();
+
+        let client_secret = NonZeroScalar::new(*prover_secret + *verifier_secret).unwrap();
+        let expected = diffie_hellman(client_secret, server_public_key.as_affine());
+        let expected: [u8; 32] = (*expected.raw_secret_bytes()).into();
+
+        let (z1, z2) = pms(prover_point, Prover::new(), verifier_point, Verifier::new()).unwrap();
+        assert_eq!(pms_bytes(z1 + z2), expected);
+
+        let (z1, z2) = pms(
+            prover_point.to_encoded_point(true),
+            Prover::new(),
+            PublicKey::from_affine(verifier_point).unwrap(),
+            Verifier::new(),
+        )
+        .unwrap();
+        assert_eq!(pms_bytes(z1 + z2), expected);
+    }
+
+    #[test]
+    fn test_point_to_p256() {
+        let scalar = NonZeroScalar::random(&mut thread_rng());
+        let point = (ProjectivePoint::GENERATOR * *scalar).to_affine();
+
+        let uncompressed = point_to_p256(point.to_encoded_point(false)).unwrap();
+        let compressed = point_to_p256(point.to_encoded_point(true)).unwrap();
+        assert_eq!(uncompressed, compressed);
+        assert_eq!(uncompressed, point_to_p256(point).unwrap());
+
+        assert_eq!(
+            point_to_p256(EncodedPoint::identity()),
+            Err(E2fError::InvalidPoint)
+        );
+    }
+}
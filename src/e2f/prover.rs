@@ -1,141 +1,375 @@
 //! The prover implementation
 
+use super::curve::ShortWeierstrassCurve;
+use super::state::{
+    Handshake5, Handshake5Slope, Handshake6, Handshake7, Handshake8, Masks, OleShares, Preprocess1,
+    Preprocess2, Preprocess3, Preprocess4, Preprocessed, SumCheck2, SumCheck3, SumCheck4,
+};
+use super::E2fError;
+use crate::func::commit::{Decommitment, HashCommitment};
 use crate::func::ole::Ole;
 use crate::func::Role;
-use mpz_share_conversion_core::fields::{p256::P256, Field, UniformRand};
+use mpz_share_conversion_core::fields::{Field, UniformRand};
 use rand::thread_rng;
+use std::marker::PhantomData;
 
-#[derive(Debug, Default)]
-pub struct Prover {
-    // Preprocess 1
-    pub(crate) a1: Option<P256>,
-    pub(crate) b1: Option<P256>,
-    pub(crate) b1_prime: Option<P256>,
-    pub(crate) r1: Option<P256>,
-
-    // Preprocess 2
-    pub(crate) a1_b2_share: Option<P256>,
-    pub(crate) a2_b1_share: Option<P256>,
-    pub(crate) a1_b2_prime_share: Option<P256>,
-    pub(crate) a2_b1_prime_share: Option<P256>,
-    pub(crate) r1_r2_share: Option<P256>,
-
-    // Preprocess 3
-    pub(crate) c1: Option<P256>,
-    pub(crate) c1_prime: Option<P256>,
-
-    // Preprocess 4
-    pub(crate) r_squared_share: Option<P256>,
-
-    // Handshake 5
-    pub(crate) ec_point: Option<(P256, P256)>,
-    pub(crate) omega_share: Option<P256>,
-
-    // Handshake 6
-    pub(crate) eta_share: Option<P256>,
-
-    // Handshake 7
-    pub(crate) z1: Option<P256>,
+#[derive(Debug)]
+pub struct Prover<S = Preprocess1> {
+    pub(crate) state: S,
 }
 
-impl Prover {
-    pub fn preprocess1(&mut self) {
+impl<C: ShortWeierstrassCurve> Default for Prover<Preprocess1<C>> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Prover<Preprocess1<C>> {
+    pub fn new() -> Self {
+        Self {
+            state: Preprocess1(PhantomData),
+        }
+    }
+
+    pub fn preprocess1(self) -> Prover<Preprocess2<C>> {
         let mut rng = thread_rng();
 
-        self.a1 = Some(P256::rand(&mut rng));
-        self.b1 = Some(P256::rand(&mut rng));
-        self.b1_prime = Some(P256::rand(&mut rng));
-        self.r1 = Some(P256::rand(&mut rng));
+        let masks = Masks {
+            a: C::Field::rand(&mut rng),
+            b: C::Field::rand(&mut rng),
+            b_prime: C::Field::rand(&mut rng),
+            r: C::Field::rand(&mut rng),
+            t: C::Field::rand(&mut rng),
+            rho: C::Field::rand(&mut rng),
+        };
+
+        Prover {
+            state: Preprocess2 { masks },
+        }
     }
+}
 
-    pub fn preprocess2_ole_input(&mut self, ole: &mut Ole<P256>) {
-        let a1 = self.a1.unwrap();
-        let b1 = self.b1.unwrap();
-        let b1_prime = self.b1_prime.unwrap();
-        let r1 = self.r1.unwrap();
+impl<C: ShortWeierstrassCurve> Prover<Preprocess2<C>> {
+    pub fn preprocess2_ole_input(&self, ole: &mut Ole<C::Field>) {
+        let Masks {
+            a: a1,
+            b: b1,
+            b_prime: b1_prime,
+            r: r1,
+            ..
+        } = self.state.masks;
 
         ole.input(Role::Sender, vec![a1, b1, a1, b1_prime, r1]);
     }
 
-    pub fn preprocess2_ole_output(&mut self, ole: &mut Ole<P256>) {
+    pub fn preprocess2_ole_output(self, ole: &mut Ole<C::Field>) -> Prover<Preprocess3<C>> {
         let output = ole.output(Role::Sender);
 
-        self.a1_b2_share = Some(output[0]);
-        self.a2_b1_share = Some(output[1]);
-        self.a1_b2_prime_share = Some(output[2]);
-        self.a2_b1_prime_share = Some(output[3]);
-        self.r1_r2_share = Some(output[4]);
+        Prover {
+            state: Preprocess3 {
+                masks: self.state.masks,
+                ole_shares: OleShares::new(output),
+            },
+        }
     }
+}
+
+impl<C: ShortWeierstrassCurve> Prover<Preprocess3<C>> {
+    pub fn preprocess3(self) -> Prover<Preprocess4<C>> {
+        let masks = self.state.masks;
+        let ole_shares = self.state.ole_shares;
+
+        let a1_b1_share = masks.a * masks.b;
+        let c1 = a1_b1_share + ole_shares.a1_b2_share + ole_shares.a2_b1_share;
+
+        let a1_b1_prime_share = masks.a * masks.b_prime;
+        let c1_prime =
+            a1_b1_prime_share + ole_shares.a1_b2_prime_share + ole_shares.a2_b1_prime_share;
+
+        Prover {
+            state: Preprocess4 {
+                masks,
+                c: c1,
+                c_prime: c1_prime,
+                r1_r2_share: ole_shares.r1_r2_share,
+            },
+        }
+    }
+}
 
-    pub fn preprocess3(&mut self) {
-        let a1_b1_share = self.a1.unwrap() * self.b1.unwrap();
-        let a1_b2_share = self.a1_b2_share.unwrap();
-        let a2_b1_share = self.a2_b1_share.unwrap();
+impl<C: ShortWeierstrassCurve> Prover<Preprocess4<C>> {
+    pub fn preprocess4(self) -> Prover<Preprocessed<C>> {
+        let r1 = self.state.masks.r;
+        let r1_squared = r1 * r1;
+
+        let two = C::Field::one() + C::Field::one();
+        let r1_r2_share = self.state.r1_r2_share;
+
+        Prover {
+            state: Preprocessed {
+                masks: self.state.masks,
+                c: self.state.c,
+                c_prime: self.state.c_prime,
+                r_squared_share: r1_squared + two * r1_r2_share,
+            },
+        }
+    }
+}
 
-        self.c1 = Some(a1_b1_share + a1_b2_share + a2_b1_share);
+impl<C: ShortWeierstrassCurve> Prover<Preprocessed<C>> {
+    /// Returns [`E2fError::InvalidPoint`] if `ec_point` is not on the curve.
+    pub fn handshake5_input_ec(
+        self,
+        ec_point: (C::Field, C::Field),
+    ) -> Result<Prover<Handshake5<C>>, E2fError> {
+        if !C::is_on_curve(ec_point) {
+            return Err(E2fError::InvalidPoint);
+        }
 
-        let a1_b1_prime_share = self.a1.unwrap() * self.b1_prime.unwrap();
-        let a1_b2_prime_share = self.a1_b2_prime_share.unwrap();
-        let a2_b1_prime_share = self.a2_b1_prime_share.unwrap();
+        Ok(Prover {
+            state: Handshake5 {
+                preprocessed: self.state,
+                ec_point,
+            },
+        })
+    }
+}
 
-        self.c1_prime = Some(a1_b1_prime_share + a1_b2_prime_share + a2_b1_prime_share);
+impl<C: ShortWeierstrassCurve> Prover<Handshake5<C>> {
+    pub fn handshake5_ole_input(&self, ole: &mut Ole<C::Field>) {
+        ole.input(Role::Sender, vec![self.state.ec_point.0]);
     }
 
-    pub fn preprocess4(&mut self) {
-        let r1_squared = self.r1.unwrap() * self.r1.unwrap();
+    pub fn handshake5_t_share_open(&self) -> C::Field {
+        self.state.preprocessed.masks.t
+    }
 
-        let two = P256::new(2).unwrap();
-        let r1_r2_share = self.r1_r2_share.unwrap();
+    pub fn handshake5_ole_output(
+        self,
+        ole: &mut Ole<C::Field>,
+        t: C::Field,
+    ) -> Prover<Handshake5Slope<C>> {
+        let x1_x2_share = ole.output(Role::Sender)[0];
+
+        Prover {
+            state: Handshake5Slope {
+                preprocessed: self.state.preprocessed,
+                ec_point: self.state.ec_point,
+                t,
+                x1_x2_share,
+            },
+        }
+    }
+}
 
-        self.r_squared_share = Some(r1_squared + two * r1_r2_share);
+impl<C: ShortWeierstrassCurve> Prover<Handshake5Slope<C>> {
+    pub fn handshake5_varepsilon1_share_open(&self) -> C::Field {
+        self.denominator_share() + -self.state.preprocessed.masks.b
     }
 
-    pub fn handshake5_input_ec(&mut self, ec_point: (P256, P256)) {
-        self.ec_point = Some(ec_point);
+    pub fn handshake5_set_omega(self, varepsilon1: C::Field) -> Prover<Handshake6<C>> {
+        let numerator_share = self.numerator_share();
+        let preprocessed = self.state.preprocessed;
+        let omega_share = varepsilon1 * preprocessed.masks.a + preprocessed.c;
+
+        Prover {
+            state: Handshake6 {
+                preprocessed,
+                ec_point: self.state.ec_point,
+                numerator_share,
+                omega_share,
+            },
+        }
     }
 
-    pub fn handshake5_varepsilon1_share_open(&self) -> P256 {
-        -self.ec_point.unwrap().0 + -self.b1.unwrap()
+    /// The prover's share of the denominator `(x2 - x1) + t * (y1 + y2)` of the slope.
+    fn denominator_share(&self) -> C::Field {
+        let (x1, y1) = self.state.ec_point;
+        let t = self.state.t;
+
+        -x1 + t * y1
     }
 
-    pub fn handshake5_set_omega(&mut self, varepsilon1: P256) {
-        self.omega_share = Some(varepsilon1 * self.a1.unwrap() + self.c1.unwrap());
+    /// The prover's share of the numerator `(y2 - y1) + t * (x1^2 + x1 * x2 + x2^2 + a)` of the
+    /// slope, where `a` is the curve parameter.
+    fn numerator_share(&self) -> C::Field {
+        let (x1, y1) = self.state.ec_point;
+        let t = self.state.t;
+        let x1_x2_share = self.state.x1_x2_share;
+
+        -y1 + t * (x1 * x1 + x1_x2_share + C::a())
     }
+}
 
-    pub fn handshake6_omega_share_open(&self) -> P256 {
-        self.omega_share.unwrap()
+impl<C: ShortWeierstrassCurve> Prover<Handshake6<C>> {
+    pub fn handshake6_omega_share_open(&self) -> C::Field {
+        self.state.omega_share
     }
 
-    pub fn handshake6_varepsilon2_share_open(&self) -> P256 {
-        -self.ec_point.unwrap().1 + -self.b1_prime.unwrap()
+    pub fn handshake6_varepsilon2_share_open(&self) -> C::Field {
+        self.state.numerator_share + -self.state.preprocessed.masks.b_prime
     }
 
-    pub fn handshake6_set_eta(&mut self, omega: P256, varepsilon2: P256) {
-        if omega == P256::new(0).unwrap() {
-            panic!("omega is 0");
+    /// Returns [`E2fError::OmegaZero`] if omega is 0, which happens if the random `a` is 0, if
+    /// the random `t` cancels the denominator or if the sum is the point at infinity.
+    pub fn handshake6_set_eta(
+        self,
+        omega: C::Field,
+        varepsilon2: C::Field,
+    ) -> Result<Prover<Handshake7<C>>, E2fError> {
+        if omega == C::Field::zero() {
+            return Err(E2fError::OmegaZero);
         }
 
         let omega_inv = omega.inverse();
-        let a1 = self.a1.unwrap();
-        let c1_prime = self.c1_prime.unwrap();
+        let preprocessed = self.state.preprocessed;
+        let a1 = preprocessed.masks.a;
+        let c1_prime = preprocessed.c_prime;
+
+        Ok(Prover {
+            state: Handshake7 {
+                preprocessed,
+                ec_point: self.state.ec_point,
+                eta_share: omega_inv * (varepsilon2 * a1 + c1_prime),
+            },
+        })
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Prover<Handshake7<C>> {
+    pub fn handshake7_varepsilon3_share_open(&self) -> C::Field {
+        self.state.eta_share + -self.state.preprocessed.masks.r
+    }
+
+    pub fn handshake7_set_z1(self, varepsilon3: C::Field) -> Prover<Handshake8<C>> {
+        let two = C::Field::one() + C::Field::one();
+        let r1 = self.state.preprocessed.masks.r;
+        let r_squared_share = self.state.preprocessed.r_squared_share;
+        let x1 = self.state.ec_point.0;
+
+        Prover {
+            state: Handshake8 {
+                ec_point: self.state.ec_point,
+                eta_share: self.state.eta_share,
+                rho: self.state.preprocessed.masks.rho,
+                z: varepsilon3 * varepsilon3 + two * varepsilon3 * r1 + r_squared_share + -x1,
+            },
+        }
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Prover<Handshake8<C>> {
+    pub fn handshake8_z1_open(&self) -> C::Field {
+        self.state.z
+    }
+
+    pub fn sum_check1_ole_input(&self, ole: &mut Ole<C::Field>) {
+        let x1 = self.state.ec_point.0;
+        let z1 = self.state.z;
+
+        ole.input(Role::Sender, vec![self.state.eta_share, x1 + -z1, z1]);
+    }
+
+    /// Computes the shares of `y3 = eta * (x1 - x3) - y1` and `x3^2`.
+    pub fn sum_check1_ole_output(self, ole: &mut Ole<C::Field>) -> Prover<SumCheck2<C>> {
+        let output = ole.output(Role::Sender);
+
+        let two = C::Field::one() + C::Field::one();
+        let (x1, y1) = self.state.ec_point;
+        let z1 = self.state.z;
+
+        Prover {
+            state: SumCheck2 {
+                rho: self.state.rho,
+                z: z1,
+                y3_share: self.state.eta_share * (x1 + -z1) + output[0] + output[1] + -y1,
+                x3_squared_share: z1 * z1 + two * output[2],
+            },
+        }
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Prover<SumCheck2<C>> {
+    pub fn sum_check2_ole_input(&self, ole: &mut Ole<C::Field>) {
+        let y3_1 = self.state.y3_share;
+        let x3_squared_share = self.state.x3_squared_share;
+        let z1 = self.state.z;
+
+        ole.input(Role::Sender, vec![y3_1, x3_squared_share, z1]);
+    }
+
+    /// Computes the share of `v = y3^2 - (x3^3 + a * x3 + b)`.
+    pub fn sum_check2_ole_output(self, ole: &mut Ole<C::Field>) -> Prover<SumCheck3<C>> {
+        let output = ole.output(Role::Sender);
+
+        let two = C::Field::one() + C::Field::one();
+        let y3_1 = self.state.y3_share;
+        let z1 = self.state.z;
+
+        let y3_squared_share = y3_1 * y3_1 + two * output[0];
+        let x3_cubed_share = self.state.x3_squared_share * z1 + output[1] + output[2];
 
-        self.eta_share = Some(omega_inv * (varepsilon2 * a1 + c1_prime));
+        // The prover subtracts the public `b`.
+        let v_share = y3_squared_share + -x3_cubed_share + -(C::a() * z1) + -C::b();
+
+        Prover {
+            state: SumCheck3 {
+                rho: self.state.rho,
+                z: z1,
+                v_share,
+            },
+        }
     }
+}
+
+impl<C: ShortWeierstrassCurve> Prover<SumCheck3<C>> {
+    pub fn sum_check3_ole_input(&self, ole: &mut Ole<C::Field>) {
+        let v1 = self.state.v_share;
+        let rho1 = self.state.rho;
 
-    pub fn handshake7_varepsilon3_share_open(&self) -> P256 {
-        self.eta_share.unwrap() + -self.r1.unwrap()
+        ole.input(Role::Sender, vec![v1, rho1]);
     }
 
-    pub fn handshake7_set_z1(&mut self, varepsilon3: P256) {
-        let two = P256::new(2).unwrap();
-        let r1 = self.r1.unwrap();
-        let r_squared_share = self.r_squared_share.unwrap();
-        let x1 = self.ec_point.unwrap().0;
+    pub fn sum_check3_ole_output(self, ole: &mut Ole<C::Field>) -> Prover<SumCheck4<C>> {
+        let output = ole.output(Role::Sender);
+
+        let w_share = self.state.v_share * self.state.rho + output[0] + output[1];
+        let (w_commitment, w_decommitment) = HashCommitment::commit(w_share);
 
-        self.z1 = Some(varepsilon3 * varepsilon3 + two * varepsilon3 * r1 + r_squared_share + -x1);
+        Prover {
+            state: SumCheck4 {
+                z: self.state.z,
+                w_commitment,
+                w_decommitment,
+            },
+        }
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Prover<SumCheck4<C>> {
+    pub fn sum_check4_w_commit(&self) -> HashCommitment {
+        self.state.w_commitment
     }
 
-    pub fn handshake8_z1_open(&self) -> P256 {
-        self.z1.unwrap()
+    pub fn sum_check4_w_share_open(&self) -> Decommitment<C::Field> {
+        self.state.w_decommitment
+    }
+
+    /// Checks the opening of the verifier's share of `w` and returns the share of the x-coordinate
+    /// if `w` is 0, which means that the sum is on the curve.
+    pub fn sum_check4_output(
+        self,
+        w_commitment: HashCommitment,
+        w_decommitment: Decommitment<C::Field>,
+    ) -> Result<C::Field, E2fError> {
+        let w_share = w_commitment
+            .verify(&w_decommitment)
+            .map_err(|_| E2fError::InvalidOpening(Role::Receiver))?;
+
+        if self.state.w_decommitment.value + w_share != C::Field::zero() {
+            return Err(E2fError::SumNotOnCurve);
+        }
+
+        Ok(self.state.z)
     }
 }
@@ -0,0 +1,159 @@
+//! The states of the E2F prover and verifier.
+//!
+//! Each state holds exactly the values, which are known in this phase of the protocol. Every step
+//! consumes the party in its current state and returns it in the next state, so the order of the
+//! steps in [`super::e2f`] is enforced at compile time:
+//!
+//! ```compile_fail
+//! use ole_protocols::e2f::{state::Preprocess1, Prover};
+//!
+//! let prover = Prover::<Preprocess1>::new().preprocess1();
+//! let _ = prover.handshake6_varepsilon2_share_open();
+//! ```
+//!
+//! The shares are named without the index of the party, because both parties use the same states.
+
+use super::curve::{P256Curve, ShortWeierstrassCurve};
+use crate::func::commit::{Decommitment, HashCommitment};
+use std::marker::PhantomData;
+
+/// The party has not sampled its masks yet.
+///
+/// All states are generic over the curve `C`, which defaults to [`P256Curve`].
+#[derive(Debug)]
+pub struct Preprocess1<C = P256Curve>(pub(crate) PhantomData<C>);
+
+/// The party has sampled its masks and runs the OLE.
+#[derive(Debug)]
+pub struct Preprocess2<C: ShortWeierstrassCurve = P256Curve> {
+    pub(crate) masks: Masks<C::Field>,
+}
+
+/// The party has its OLE output and computes its shares of `c` and `c'`.
+#[derive(Debug)]
+pub struct Preprocess3<C: ShortWeierstrassCurve = P256Curve> {
+    pub(crate) masks: Masks<C::Field>,
+    pub(crate) ole_shares: OleShares<C::Field>,
+}
+
+/// The party computes its share of `r^2`.
+#[derive(Debug)]
+pub struct Preprocess4<C: ShortWeierstrassCurve = P256Curve> {
+    pub(crate) masks: Masks<C::Field>,
+    pub(crate) c: C::Field,
+    pub(crate) c_prime: C::Field,
+    pub(crate) r1_r2_share: C::Field,
+}
+
+/// The preprocessing is done and the party waits for its EC point.
+#[derive(Debug)]
+pub struct Preprocessed<C: ShortWeierstrassCurve = P256Curve> {
+    pub(crate) masks: Masks<C::Field>,
+    pub(crate) c: C::Field,
+    pub(crate) c_prime: C::Field,
+    pub(crate) r_squared_share: C::Field,
+}
+
+/// The party has its EC point, runs the OLE for `x1 * x2` and opens its share of `t`.
+#[derive(Debug)]
+pub struct Handshake5<C: ShortWeierstrassCurve = P256Curve> {
+    pub(crate) preprocessed: Preprocessed<C>,
+    pub(crate) ec_point: (C::Field, C::Field),
+}
+
+/// The party has its share of `x1 * x2` and opens its share of `varepsilon1`.
+#[derive(Debug)]
+pub struct Handshake5Slope<C: ShortWeierstrassCurve = P256Curve> {
+    pub(crate) preprocessed: Preprocessed<C>,
+    pub(crate) ec_point: (C::Field, C::Field),
+    pub(crate) t: C::Field,
+    pub(crate) x1_x2_share: C::Field,
+}
+
+/// The party has its share of `omega` and opens it together with its share of `varepsilon2`.
+#[derive(Debug)]
+pub struct Handshake6<C: ShortWeierstrassCurve = P256Curve> {
+    pub(crate) preprocessed: Preprocessed<C>,
+    pub(crate) ec_point: (C::Field, C::Field),
+    pub(crate) numerator_share: C::Field,
+    pub(crate) omega_share: C::Field,
+}
+
+/// The party has its share of `eta` and opens its share of `varepsilon3`.
+#[derive(Debug)]
+pub struct Handshake7<C: ShortWeierstrassCurve = P256Curve> {
+    pub(crate) preprocessed: Preprocessed<C>,
+    pub(crate) ec_point: (C::Field, C::Field),
+    pub(crate) eta_share: C::Field,
+}
+
+/// The party holds its share of the x-coordinate. Optionally, the parties check that the sum is
+/// on the curve, which starts with the OLE for their shares of `y3` and `x3^2`.
+#[derive(Debug)]
+pub struct Handshake8<C: ShortWeierstrassCurve = P256Curve> {
+    pub(crate) ec_point: (C::Field, C::Field),
+    pub(crate) eta_share: C::Field,
+    pub(crate) rho: C::Field,
+    pub(crate) z: C::Field,
+}
+
+/// The party has its shares of `y3` and `x3^2` and runs the OLE for `y3^2` and `x3^3`.
+#[derive(Debug)]
+pub struct SumCheck2<C: ShortWeierstrassCurve = P256Curve> {
+    pub(crate) rho: C::Field,
+    pub(crate) z: C::Field,
+    pub(crate) y3_share: C::Field,
+    pub(crate) x3_squared_share: C::Field,
+}
+
+/// The party has its share of `v = y3^2 - (x3^3 + a * x3 + b)` and runs the OLE for `rho * v`.
+#[derive(Debug)]
+pub struct SumCheck3<C: ShortWeierstrassCurve = P256Curve> {
+    pub(crate) rho: C::Field,
+    pub(crate) z: C::Field,
+    pub(crate) v_share: C::Field,
+}
+
+/// The party has committed to its share of `w = rho * v`, which is 0 if the sum is on the curve,
+/// and opens it.
+#[derive(Debug)]
+pub struct SumCheck4<C: ShortWeierstrassCurve = P256Curve> {
+    pub(crate) z: C::Field,
+    pub(crate) w_commitment: HashCommitment,
+    pub(crate) w_decommitment: Decommitment<C::Field>,
+}
+
+/// The random masks of a party.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Masks<F> {
+    pub(crate) a: F,
+    pub(crate) b: F,
+    pub(crate) b_prime: F,
+    pub(crate) r: F,
+    pub(crate) t: F,
+    pub(crate) rho: F,
+}
+
+/// The shares of the cross terms, which are computed with the OLE.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct OleShares<F> {
+    pub(crate) a1_b2_share: F,
+    pub(crate) a2_b1_share: F,
+    pub(crate) a1_b2_prime_share: F,
+    pub(crate) a2_b1_prime_share: F,
+    pub(crate) r1_r2_share: F,
+}
+
+impl<F: Copy> OleShares<F> {
+    pub(crate) fn new(output: Vec<F>) -> Self {
+        assert_eq!(output.len(), 5);
+
+        Self {
+            a1_b2_share: output[0],
+            a2_b1_share: output[1],
+            a1_b2_prime_share: output[2],
+            a2_b1_prime_share: output[3],
+            r1_r2_share: output[4],
+        }
+    }
+}
@@ -1,141 +1,373 @@
 //! The verifier implementation
 
+use super::curve::ShortWeierstrassCurve;
+use super::state::{
+    Handshake5, Handshake5Slope, Handshake6, Handshake7, Handshake8, Masks, OleShares, Preprocess1,
+    Preprocess2, Preprocess3, Preprocess4, Preprocessed, SumCheck2, SumCheck3, SumCheck4,
+};
+use super::E2fError;
+use crate::func::commit::{Decommitment, HashCommitment};
 use crate::func::ole::Ole;
 use crate::func::Role;
-use mpz_share_conversion_core::fields::{p256::P256, Field, UniformRand};
+use mpz_share_conversion_core::fields::{Field, UniformRand};
 use rand::thread_rng;
+use std::marker::PhantomData;
 
-#[derive(Debug, Default)]
-pub struct Verifier {
-    // Preprocess 1
-    pub(crate) a2: Option<P256>,
-    pub(crate) b2: Option<P256>,
-    pub(crate) b2_prime: Option<P256>,
-    pub(crate) r2: Option<P256>,
-
-    // Preprocess 2
-    pub(crate) a1_b2_share: Option<P256>,
-    pub(crate) a2_b1_share: Option<P256>,
-    pub(crate) a1_b2_prime_share: Option<P256>,
-    pub(crate) a2_b1_prime_share: Option<P256>,
-    pub(crate) r1_r2_share: Option<P256>,
-
-    // Preprocess 3
-    pub(crate) c2: Option<P256>,
-    pub(crate) c2_prime: Option<P256>,
-
-    // Preprocess 4
-    pub(crate) r_squared_share: Option<P256>,
-
-    // Handshake 5
-    pub(crate) ec_point: Option<(P256, P256)>,
-    pub(crate) omega_share: Option<P256>,
-
-    // Handshake 6
-    pub(crate) eta_share: Option<P256>,
-
-    // Handshake 7
-    pub(crate) z2: Option<P256>,
+#[derive(Debug)]
+pub struct Verifier<S = Preprocess1> {
+    pub(crate) state: S,
 }
 
-impl Verifier {
-    pub fn preprocess1(&mut self) {
+impl<C: ShortWeierstrassCurve> Default for Verifier<Preprocess1<C>> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Verifier<Preprocess1<C>> {
+    pub fn new() -> Self {
+        Self {
+            state: Preprocess1(PhantomData),
+        }
+    }
+
+    pub fn preprocess1(self) -> Verifier<Preprocess2<C>> {
         let mut rng = thread_rng();
 
-        self.a2 = Some(P256::rand(&mut rng));
-        self.b2 = Some(P256::rand(&mut rng));
-        self.b2_prime = Some(P256::rand(&mut rng));
-        self.r2 = Some(P256::rand(&mut rng));
+        let masks = Masks {
+            a: C::Field::rand(&mut rng),
+            b: C::Field::rand(&mut rng),
+            b_prime: C::Field::rand(&mut rng),
+            r: C::Field::rand(&mut rng),
+            t: C::Field::rand(&mut rng),
+            rho: C::Field::rand(&mut rng),
+        };
+
+        Verifier {
+            state: Preprocess2 { masks },
+        }
     }
+}
 
-    pub fn preprocess2_ole_input(&mut self, ole: &mut Ole<P256>) {
-        let a2 = self.a2.unwrap();
-        let b2 = self.b2.unwrap();
-        let b2_prime = self.b2_prime.unwrap();
-        let r2 = self.r2.unwrap();
+impl<C: ShortWeierstrassCurve> Verifier<Preprocess2<C>> {
+    pub fn preprocess2_ole_input(&self, ole: &mut Ole<C::Field>) {
+        let Masks {
+            a: a2,
+            b: b2,
+            b_prime: b2_prime,
+            r: r2,
+            ..
+        } = self.state.masks;
 
         ole.input(Role::Receiver, vec![b2, a2, b2_prime, a2, r2]);
     }
 
-    pub fn preprocess2_ole_output(&mut self, ole: &mut Ole<P256>) {
+    pub fn preprocess2_ole_output(self, ole: &mut Ole<C::Field>) -> Verifier<Preprocess3<C>> {
         let output = ole.output(Role::Receiver);
 
-        self.a1_b2_share = Some(output[0]);
-        self.a2_b1_share = Some(output[1]);
-        self.a1_b2_prime_share = Some(output[2]);
-        self.a2_b1_prime_share = Some(output[3]);
-        self.r1_r2_share = Some(output[4]);
+        Verifier {
+            state: Preprocess3 {
+                masks: self.state.masks,
+                ole_shares: OleShares::new(output),
+            },
+        }
     }
+}
 
-    pub fn preprocess3(&mut self) {
-        let a2_b2_share = self.a2.unwrap() * self.b2.unwrap();
-        let a1_b2_share = self.a1_b2_share.unwrap();
-        let a2_b1_share = self.a2_b1_share.unwrap();
+impl<C: ShortWeierstrassCurve> Verifier<Preprocess3<C>> {
+    pub fn preprocess3(self) -> Verifier<Preprocess4<C>> {
+        let masks = self.state.masks;
+        let ole_shares = self.state.ole_shares;
+
+        let a2_b2_share = masks.a * masks.b;
+        let c2 = a2_b2_share + ole_shares.a1_b2_share + ole_shares.a2_b1_share;
+
+        let a2_b2_prime_share = masks.a * masks.b_prime;
+        let c2_prime =
+            a2_b2_prime_share + ole_shares.a1_b2_prime_share + ole_shares.a2_b1_prime_share;
+
+        Verifier {
+            state: Preprocess4 {
+                masks,
+                c: c2,
+                c_prime: c2_prime,
+                r1_r2_share: ole_shares.r1_r2_share,
+            },
+        }
+    }
+}
 
-        self.c2 = Some(a2_b2_share + a1_b2_share + a2_b1_share);
+impl<C: ShortWeierstrassCurve> Verifier<Preprocess4<C>> {
+    pub fn preprocess4(self) -> Verifier<Preprocessed<C>> {
+        let r2 = self.state.masks.r;
+        let r2_squared = r2 * r2;
+
+        let two = C::Field::one() + C::Field::one();
+        let r1_r2_share = self.state.r1_r2_share;
+
+        Verifier {
+            state: Preprocessed {
+                masks: self.state.masks,
+                c: self.state.c,
+                c_prime: self.state.c_prime,
+                r_squared_share: r2_squared + two * r1_r2_share,
+            },
+        }
+    }
+}
 
-        let a2_b2_prime_share = self.a2.unwrap() * self.b2_prime.unwrap();
-        let a1_b2_prime_share = self.a1_b2_prime_share.unwrap();
-        let a2_b1_prime_share = self.a2_b1_prime_share.unwrap();
+impl<C: ShortWeierstrassCurve> Verifier<Preprocessed<C>> {
+    /// Returns [`E2fError::InvalidPoint`] if `ec_point` is not on the curve.
+    pub fn handshake5_input_ec(
+        self,
+        ec_point: (C::Field, C::Field),
+    ) -> Result<Verifier<Handshake5<C>>, E2fError> {
+        if !C::is_on_curve(ec_point) {
+            return Err(E2fError::InvalidPoint);
+        }
 
-        self.c2_prime = Some(a2_b2_prime_share + a1_b2_prime_share + a2_b1_prime_share);
+        Ok(Verifier {
+            state: Handshake5 {
+                preprocessed: self.state,
+                ec_point,
+            },
+        })
     }
+}
 
-    pub fn preprocess4(&mut self) {
-        let r2_squared = self.r2.unwrap() * self.r2.unwrap();
+impl<C: ShortWeierstrassCurve> Verifier<Handshake5<C>> {
+    pub fn handshake5_ole_input(&self, ole: &mut Ole<C::Field>) {
+        ole.input(Role::Receiver, vec![self.state.ec_point.0]);
+    }
 
-        let two = P256::new(2).unwrap();
-        let r1_r2_share = self.r1_r2_share.unwrap();
+    pub fn handshake5_t_share_open(&self) -> C::Field {
+        self.state.preprocessed.masks.t
+    }
 
-        self.r_squared_share = Some(r2_squared + two * r1_r2_share);
+    pub fn handshake5_ole_output(
+        self,
+        ole: &mut Ole<C::Field>,
+        t: C::Field,
+    ) -> Verifier<Handshake5Slope<C>> {
+        let x1_x2_share = ole.output(Role::Receiver)[0];
+
+        Verifier {
+            state: Handshake5Slope {
+                preprocessed: self.state.preprocessed,
+                ec_point: self.state.ec_point,
+                t,
+                x1_x2_share,
+            },
+        }
     }
+}
 
-    pub fn handshake5_input_ec(&mut self, ec_point: (P256, P256)) {
-        self.ec_point = Some(ec_point);
+impl<C: ShortWeierstrassCurve> Verifier<Handshake5Slope<C>> {
+    pub fn handshake5_varepsilon1_share_open(&self) -> C::Field {
+        self.denominator_share() + -self.state.preprocessed.masks.b
     }
 
-    pub fn handshake5_varepsilon1_share_open(&self) -> P256 {
-        self.ec_point.unwrap().0 + -self.b2.unwrap()
+    pub fn handshake5_set_omega(self, varepsilon1: C::Field) -> Verifier<Handshake6<C>> {
+        let numerator_share = self.numerator_share();
+        let preprocessed = self.state.preprocessed;
+        let omega_share = varepsilon1 * preprocessed.masks.a + preprocessed.c;
+
+        Verifier {
+            state: Handshake6 {
+                preprocessed,
+                ec_point: self.state.ec_point,
+                numerator_share,
+                omega_share,
+            },
+        }
     }
 
-    pub fn handshake5_set_omega(&mut self, varepsilon1: P256) {
-        self.omega_share = Some(varepsilon1 * self.a2.unwrap() + self.c2.unwrap());
+    /// The verifier's share of the denominator `(x2 - x1) + t * (y1 + y2)` of the slope.
+    fn denominator_share(&self) -> C::Field {
+        let (x2, y2) = self.state.ec_point;
+        let t = self.state.t;
+
+        x2 + t * y2
     }
 
-    pub fn handshake6_omega_share_open(&self) -> P256 {
-        self.omega_share.unwrap()
+    /// The verifier's share of the numerator `(y2 - y1) + t * (x1^2 + x1 * x2 + x2^2 + a)` of the
+    /// slope. The curve parameter `a` is added by the prover.
+    fn numerator_share(&self) -> C::Field {
+        let (x2, y2) = self.state.ec_point;
+        let t = self.state.t;
+        let x1_x2_share = self.state.x1_x2_share;
+
+        y2 + t * (x2 * x2 + x1_x2_share)
     }
+}
 
-    pub fn handshake6_varepsilon2_share_open(&self) -> P256 {
-        self.ec_point.unwrap().1 + -self.b2_prime.unwrap()
+impl<C: ShortWeierstrassCurve> Verifier<Handshake6<C>> {
+    pub fn handshake6_omega_share_open(&self) -> C::Field {
+        self.state.omega_share
     }
 
-    pub fn handshake6_set_eta(&mut self, omega: P256, varepsilon2: P256) {
-        if omega == P256::new(0).unwrap() {
-            panic!("omega is 0");
+    pub fn handshake6_varepsilon2_share_open(&self) -> C::Field {
+        self.state.numerator_share + -self.state.preprocessed.masks.b_prime
+    }
+
+    /// Returns [`E2fError::OmegaZero`] if omega is 0, which happens if the random `a` is 0, if
+    /// the random `t` cancels the denominator or if the sum is the point at infinity.
+    pub fn handshake6_set_eta(
+        self,
+        omega: C::Field,
+        varepsilon2: C::Field,
+    ) -> Result<Verifier<Handshake7<C>>, E2fError> {
+        if omega == C::Field::zero() {
+            return Err(E2fError::OmegaZero);
         }
 
         let omega_inv = omega.inverse();
-        let a2 = self.a2.unwrap();
-        let c2_prime = self.c2_prime.unwrap();
+        let preprocessed = self.state.preprocessed;
+        let a2 = preprocessed.masks.a;
+        let c2_prime = preprocessed.c_prime;
+
+        Ok(Verifier {
+            state: Handshake7 {
+                preprocessed,
+                ec_point: self.state.ec_point,
+                eta_share: omega_inv * (varepsilon2 * a2 + c2_prime),
+            },
+        })
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Verifier<Handshake7<C>> {
+    pub fn handshake7_varepsilon3_share_open(&self) -> C::Field {
+        self.state.eta_share + -self.state.preprocessed.masks.r
+    }
 
-        self.eta_share = Some(omega_inv * (varepsilon2 * a2 + c2_prime));
+    pub fn handshake7_set_z2(self, varepsilon3: C::Field) -> Verifier<Handshake8<C>> {
+        let two = C::Field::one() + C::Field::one();
+        let r2 = self.state.preprocessed.masks.r;
+        let r_squared_share = self.state.preprocessed.r_squared_share;
+        let x2 = self.state.ec_point.0;
+
+        Verifier {
+            state: Handshake8 {
+                ec_point: self.state.ec_point,
+                eta_share: self.state.eta_share,
+                rho: self.state.preprocessed.masks.rho,
+                z: two * varepsilon3 * r2 + r_squared_share + -x2,
+            },
+        }
     }
+}
 
-    pub fn handshake7_varepsilon3_share_open(&self) -> P256 {
-        self.eta_share.unwrap() + -self.r2.unwrap()
+impl<C: ShortWeierstrassCurve> Verifier<Handshake8<C>> {
+    pub fn handshake8_z2_open(&self) -> C::Field {
+        self.state.z
     }
 
-    pub fn handshake7_set_z2(&mut self, varepsilon3: P256) {
-        let two = P256::new(2).unwrap();
-        let r2 = self.r2.unwrap();
-        let r_squared_share = self.r_squared_share.unwrap();
-        let x2 = self.ec_point.unwrap().0;
+    pub fn sum_check1_ole_input(&self, ole: &mut Ole<C::Field>) {
+        let z2 = self.state.z;
 
-        self.z2 = Some(two * varepsilon3 * r2 + r_squared_share + -x2);
+        // The prover holds x1, so the share of the verifier of `x1 - x3` is `-z2`.
+        ole.input(Role::Receiver, vec![-z2, self.state.eta_share, z2]);
     }
 
-    pub fn handshake8_z2_open(&self) -> P256 {
-        self.z2.unwrap()
+    /// Computes the shares of `y3 = eta * (x1 - x3) - y1` and `x3^2`.
+    pub fn sum_check1_ole_output(self, ole: &mut Ole<C::Field>) -> Verifier<SumCheck2<C>> {
+        let output = ole.output(Role::Receiver);
+
+        let two = C::Field::one() + C::Field::one();
+        let z2 = self.state.z;
+
+        Verifier {
+            state: SumCheck2 {
+                rho: self.state.rho,
+                z: z2,
+                y3_share: self.state.eta_share * -z2 + output[0] + output[1],
+                x3_squared_share: z2 * z2 + two * output[2],
+            },
+        }
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Verifier<SumCheck2<C>> {
+    pub fn sum_check2_ole_input(&self, ole: &mut Ole<C::Field>) {
+        let y3_2 = self.state.y3_share;
+        let x3_squared_share = self.state.x3_squared_share;
+        let z2 = self.state.z;
+
+        ole.input(Role::Receiver, vec![y3_2, z2, x3_squared_share]);
+    }
+
+    /// Computes the share of `v = y3^2 - (x3^3 + a * x3 + b)`.
+    pub fn sum_check2_ole_output(self, ole: &mut Ole<C::Field>) -> Verifier<SumCheck3<C>> {
+        let output = ole.output(Role::Receiver);
+
+        let two = C::Field::one() + C::Field::one();
+        let y3_2 = self.state.y3_share;
+        let z2 = self.state.z;
+
+        let y3_squared_share = y3_2 * y3_2 + two * output[0];
+        let x3_cubed_share = self.state.x3_squared_share * z2 + output[1] + output[2];
+
+        let v_share = y3_squared_share + -x3_cubed_share + -(C::a() * z2);
+
+        Verifier {
+            state: SumCheck3 {
+                rho: self.state.rho,
+                z: z2,
+                v_share,
+            },
+        }
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Verifier<SumCheck3<C>> {
+    pub fn sum_check3_ole_input(&self, ole: &mut Ole<C::Field>) {
+        let v2 = self.state.v_share;
+        let rho2 = self.state.rho;
+
+        ole.input(Role::Receiver, vec![rho2, v2]);
+    }
+
+    pub fn sum_check3_ole_output(self, ole: &mut Ole<C::Field>) -> Verifier<SumCheck4<C>> {
+        let output = ole.output(Role::Receiver);
+
+        let w_share = self.state.v_share * self.state.rho + output[0] + output[1];
+        let (w_commitment, w_decommitment) = HashCommitment::commit(w_share);
+
+        Verifier {
+            state: SumCheck4 {
+                z: self.state.z,
+                w_commitment,
+                w_decommitment,
+            },
+        }
+    }
+}
+
+impl<C: ShortWeierstrassCurve> Verifier<SumCheck4<C>> {
+    pub fn sum_check4_w_commit(&self) -> HashCommitment {
+        self.state.w_commitment
+    }
+
+    pub fn sum_check4_w_share_open(&self) -> Decommitment<C::Field> {
+        self.state.w_decommitment
+    }
+
+    /// Checks the opening of the prover's share of `w` and returns the share of the x-coordinate
+    /// if `w` is 0, which means that the sum is on the curve.
+    pub fn sum_check4_output(
+        self,
+        w_commitment: HashCommitment,
+        w_decommitment: Decommitment<C::Field>,
+    ) -> Result<C::Field, E2fError> {
+        let w_share = w_commitment
+            .verify(&w_decommitment)
+            .map_err(|_| E2fError::InvalidOpening(Role::Sender))?;
+
+        if self.state.w_decommitment.value + w_share != C::Field::zero() {
+            return Err(E2fError::SumNotOnCurve);
+        }
+
+        Ok(self.state.z)
     }
 }
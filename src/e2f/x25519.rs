@@ -0,0 +1,175 @@
+//! Converts the sum of two Curve25519 points into additive shares of its u-coordinate, which is
+//! the shared secret of X25519.
+//!
+//! Curve25519 is the Montgomery curve `v^2 = u^3 + A * u^2 + u` with `A = 486662`. The map
+//! `(u, v) -> (u + A / 3, v)` is an isomorphism onto the short Weierstrass curve
+//! `y^2 = x^3 + a * x + b` with `a = (3 - A^2) / 3` and `b = (2 * A^3 - 9 * A) / 27`. So both
+//! parties map their points locally, run [`super::e2f`] on the Weierstrass curve, and the prover
+//! subtracts `A / 3` from its share of the x-coordinate.
+
+use super::{curve::ShortWeierstrassCurve, e2f, state::Preprocess1, E2fError, Prover, Verifier};
+use crate::f25519::F25519;
+use mpz_share_conversion_core::Field;
+
+/// The coefficient `A` of Curve25519.
+const MONTGOMERY_A: u128 = 486662;
+
+/// The v-coordinate of the Curve25519 base point, little-endian.
+const BASE_POINT_V: [u8; 32] = [
+    0xd9, 0xd3, 0xce, 0x7e, 0xa2, 0xc5, 0xe9, 0x29, 0xb2, 0x61, 0x7c, 0x6d, 0x7e, 0x4d, 0x3d, 0x92,
+    0x4c, 0xd1, 0x48, 0x77, 0x2c, 0xdd, 0x1e, 0xe0, 0xb4, 0x86, 0xa0, 0xb8, 0xa1, 0x19, 0xae, 0x20,
+];
+
+/// Returns the u-coordinate shares of the sum of the two Curve25519 points, which are given in
+/// Montgomery coordinates `(u, v)`.
+///
+/// Unlike the u-only X25519 ladder, this needs the v-coordinates, and both parties must take them
+/// from the same convention: `(u, v)` and `(u, -v)` are inverse points, so if one party flips the
+/// sign of its `v`, the result is the u-coordinate of the difference of the two points instead.
+pub fn x25519(
+    prover_point: (F25519, F25519),
+    prover: Prover<Preprocess1<Wei25519Curve>>,
+    verifier_point: (F25519, F25519),
+    verifier: Verifier<Preprocess1<Wei25519Curve>>,
+) -> Result<(F25519, F25519), E2fError> {
+    let (z1, z2) = e2f(
+        to_weierstrass(prover_point),
+        prover,
+        to_weierstrass(verifier_point),
+        verifier,
+    )?;
+
+    Ok((z1 + -a_over_three(), z2))
+}
+
+/// Maps a point from Montgomery coordinates `(u, v)` to the short Weierstrass curve.
+///
+/// The sign of `v` is kept, so points passed to [`x25519`] by both parties must use the same sign
+/// convention for `v`.
+pub fn to_weierstrass((u, v): (F25519, F25519)) -> (F25519, F25519) {
+    (u + a_over_three(), v)
+}
+
+fn a_over_three() -> F25519 {
+    F25519::new(MONTGOMERY_A) * F25519::new(3).inverse()
+}
+
+/// The short Weierstrass form of Curve25519.
+#[derive(Debug, Clone, Copy)]
+pub struct Wei25519Curve;
+
+impl ShortWeierstrassCurve for Wei25519Curve {
+    type Field = F25519;
+
+    fn a() -> F25519 {
+        let a = F25519::new(MONTGOMERY_A);
+        let three = F25519::new(3);
+
+        (three + -(a * a)) * three.inverse()
+    }
+
+    fn b() -> F25519 {
+        let a = F25519::new(MONTGOMERY_A);
+        let nine = F25519::new(9);
+
+        (F25519::new(2) * a * a * a + -(nine * a)) * F25519::new(27).inverse()
+    }
+
+    /// The image of the Curve25519 base point `u = 9`.
+    fn generator() -> (F25519, F25519) {
+        to_weierstrass((
+            F25519::new(9),
+            F25519::from_le_bytes_mod_order(&BASE_POINT_V),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::{constants::ED25519_BASEPOINT_POINT, EdwardsPoint, Scalar};
+    use rand::{thread_rng, Rng};
+
+    #[test]
+    fn test_x25519() {
+        let prover_point = random_point();
+        let verifier_point = random_point();
+
+        let prover_mont = edwards_to_montgomery(&prover_point);
+        let verifier_mont = edwards_to_montgomery(&verifier_point);
+        assert!(Wei25519Curve::is_on_curve(to_weierstrass(prover_mont)));
+
+        let (z1, z2) = x25519(prover_mont, Prover::new(), verifier_mont, Verifier::new()).unwrap();
+
+        let u_expected = (prover_point + verifier_point).to_montgomery().to_bytes();
+        assert_eq!(z1 + z2, F25519::from_le_bytes_mod_order(&u_expected));
+    }
+
+    #[test]
+    fn test_x25519_flipped_v() {
+        let prover_point = random_point();
+        let verifier_point = random_point();
+
+        let prover_mont = edwards_to_montgomery(&prover_point);
+        let (u, v) = edwards_to_montgomery(&verifier_point);
+
+        let (z1, z2) = x25519(prover_mont, Prover::new(), (u, -v), Verifier::new()).unwrap();
+
+        let u_sum = (prover_point + verifier_point).to_montgomery().to_bytes();
+        let u_difference = (prover_point - verifier_point).to_montgomery().to_bytes();
+        assert_ne!(z1 + z2, F25519::from_le_bytes_mod_order(&u_sum));
+        assert_eq!(z1 + z2, F25519::from_le_bytes_mod_order(&u_difference));
+    }
+
+    #[test]
+    fn test_wei25519_generator() {
+        let (x, y) = to_weierstrass(edwards_to_montgomery(&ED25519_BASEPOINT_POINT));
+        let generator = Wei25519Curve::generator();
+
+        // The sign of v depends on the choice of sqrt(-(A + 2)) in `edwards_to_montgomery`.
+        assert_eq!(x, generator.0);
+        assert!(y == generator.1 || y == -generator.1);
+        assert!(Wei25519Curve::is_on_curve(generator));
+    }
+
+    #[test]
+    fn test_edwards_to_montgomery() {
+        let point = random_point();
+        let (u, _) = edwards_to_montgomery(&point);
+
+        let u_expected = point.to_montgomery().to_bytes();
+        assert_eq!(u, F25519::from_le_bytes_mod_order(&u_expected));
+    }
+
+    fn random_point() -> EdwardsPoint {
+        let scalar = Scalar::from_bytes_mod_order(thread_rng().gen());
+        ED25519_BASEPOINT_POINT * scalar
+    }
+
+    /// Returns the Montgomery coordinates `(u, v)` of an Edwards point.
+    fn edwards_to_montgomery(point: &EdwardsPoint) -> (F25519, F25519) {
+        let one = F25519::one();
+
+        // The compressed point is y with the sign of x in the top bit.
+        let mut y_bytes = point.compress().to_bytes();
+        let x_is_negative = y_bytes[31] >> 7 == 1;
+        y_bytes[31] &= 0x7f;
+        let y = F25519::from_le_bytes_mod_order(&y_bytes);
+
+        // -x^2 + y^2 = 1 + d * x^2 * y^2 with d = -121665 / 121666
+        let d = -F25519::new(121665) * F25519::new(121666).inverse();
+        let mut x = ((y * y + -one) * (d * y * y + one).inverse())
+            .sqrt()
+            .unwrap();
+        if (x.to_le_bytes()[0] & 1 == 1) != x_is_negative {
+            x = -x;
+        }
+
+        // u = (1 + y) / (1 - y) and v = sqrt(-(A + 2)) * u / x
+        let u = (one + y) * (one + -y).inverse();
+        let scale = (-F25519::new(MONTGOMERY_A + 2)).sqrt().unwrap();
+        let v = scale * u * x.inverse();
+
+        (u, v)
+    }
+}
@@ -0,0 +1,30 @@
+use crate::prime_field::prime_field;
+
+prime_field!(
+    /// The field GF(2^130 - 5), which is used by Poly1305
+    F1305,
+    modulus = "1361129467683753853853498429727072845819",
+    generator = "2",
+    limbs = 3,
+    bits = 130,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mpz_share_conversion_core::fields::{Field, UniformRand};
+    use rand::thread_rng;
+
+    #[test]
+    fn test_f1305() {
+        let mut rng = thread_rng();
+        let a = F1305::rand(&mut rng);
+
+        // 2^130 = 5 mod p
+        assert_eq!(F1305::two_pow(130), F1305::new(5));
+        assert_eq!(F1305::two_pow(129) + F1305::two_pow(129), F1305::new(5));
+        assert_eq!(a * a.inverse(), F1305::one());
+        assert_eq!(a + -a, F1305::zero());
+        assert_eq!(F1305::from_le_bytes_mod_order(&a.to_le_bytes()), a);
+    }
+}
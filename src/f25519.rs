@@ -0,0 +1,41 @@
+use crate::prime_field::prime_field;
+
+prime_field!(
+    /// The field GF(2^255 - 19), which is the base field of Curve25519
+    F25519,
+    modulus = "57896044618658097711785492504343953926634992332820282019728792003956564819949",
+    generator = "2",
+    limbs = 4,
+    bits = 255,
+);
+
+impl F25519 {
+    /// Returns a square root, if there is one.
+    pub fn sqrt(&self) -> Option<Self> {
+        use ark_ff::Field;
+
+        self.inner.sqrt().map(|inner| Self { inner })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mpz_share_conversion_core::fields::{Field, UniformRand};
+    use rand::thread_rng;
+
+    #[test]
+    fn test_f25519() {
+        let mut rng = thread_rng();
+        let a = F25519::rand(&mut rng);
+
+        // 2^255 = 19 mod p
+        assert_eq!(F25519::two_pow(255), F25519::new(19));
+        assert_eq!(a * a.inverse(), F25519::one());
+        assert_eq!(a + -a, F25519::zero());
+        assert_eq!(F25519::from_le_bytes_mod_order(&a.to_le_bytes()), a);
+
+        let root = (a * a).sqrt().unwrap();
+        assert!(root == a || root == -a);
+    }
+}
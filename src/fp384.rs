@@ -0,0 +1,30 @@
+use crate::prime_field::prime_field;
+
+prime_field!(
+    /// The base field of the NIST curve P-384 with modulus `2^384 - 2^128 - 2^96 + 2^32 - 1`
+    P384,
+    modulus = "39402006196394479212279040100143613805079739270465446667948293404245721771496870329047266088258938001861606973112319",
+    generator = "19",
+    limbs = 6,
+    bits = 384,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mpz_share_conversion_core::fields::{Field, UniformRand};
+    use rand::thread_rng;
+
+    #[test]
+    fn test_p384() {
+        let mut rng = thread_rng();
+        let a = P384::rand(&mut rng);
+
+        // 2^384 = 2^128 + 2^96 - 2^32 + 1 mod p
+        let reduced = P384::two_pow(128) + P384::two_pow(96) + -P384::two_pow(32) + P384::one();
+        assert_eq!(P384::two_pow(384), reduced);
+        assert_eq!(a * a.inverse(), P384::one());
+        assert_eq!(a + -a, P384::zero());
+        assert_eq!(P384::from_le_bytes_mod_order(&a.to_le_bytes()), a);
+    }
+}
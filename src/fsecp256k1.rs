@@ -0,0 +1,32 @@
+use crate::prime_field::prime_field;
+
+prime_field!(
+    /// The base field of the curve secp256k1 with modulus `2^256 - 2^32 - 977`
+    Secp256k1,
+    modulus = "115792089237316195423570985008687907853269984665640564039457584007908834671663",
+    generator = "3",
+    limbs = 4,
+    bits = 256,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mpz_share_conversion_core::fields::{Field, UniformRand};
+    use rand::thread_rng;
+
+    #[test]
+    fn test_secp256k1() {
+        let mut rng = thread_rng();
+        let a = Secp256k1::rand(&mut rng);
+
+        // 2^256 = 2^32 + 977 mod p
+        assert_eq!(
+            Secp256k1::two_pow(256),
+            Secp256k1::two_pow(32) + Secp256k1::new(977)
+        );
+        assert_eq!(a * a.inverse(), Secp256k1::one());
+        assert_eq!(a + -a, Secp256k1::zero());
+        assert_eq!(Secp256k1::from_le_bytes_mod_order(&a.to_le_bytes()), a);
+    }
+}
@@ -0,0 +1,160 @@
+//! This module implements a commitment functionality and a hash-based commitment scheme.
+//!
+//! Committing to a value before the other party reveals anything prevents a rushing party from
+//! choosing its value depending on the value of the other party.
+
+use super::Role;
+use mpz_share_conversion_core::Field;
+use rand::{thread_rng, Rng};
+use sha2::{Digest, Sha256};
+
+/// An ideal commitment functionality, which only opens the commitments once both parties have
+/// committed.
+#[derive(Debug)]
+pub struct Commit<T: Field> {
+    value_sender: Option<T>,
+    value_receiver: Option<T>,
+}
+
+impl<T: Field> Default for Commit<T> {
+    fn default() -> Self {
+        Self {
+            value_sender: None,
+            value_receiver: None,
+        }
+    }
+}
+
+impl<T: Field> Commit<T> {
+    pub fn commit(&mut self, role: Role, value: T) {
+        let slot = match role {
+            Role::Sender => &mut self.value_sender,
+            Role::Receiver => &mut self.value_receiver,
+        };
+
+        assert!(slot.is_none(), "{role:?} has already committed");
+        *slot = Some(value);
+    }
+
+    /// Opens the value committed by `role`.
+    pub fn open(&self, role: Role) -> Result<T, CommitError> {
+        let sender = self
+            .value_sender
+            .ok_or(CommitError::NotCommitted(Role::Sender))?;
+        let receiver = self
+            .value_receiver
+            .ok_or(CommitError::NotCommitted(Role::Receiver))?;
+
+        match role {
+            Role::Sender => Ok(sender),
+            Role::Receiver => Ok(receiver),
+        }
+    }
+}
+
+/// A commitment `SHA-256(randomness || value)` to a field element.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HashCommitment([u8; 32]);
+
+/// The opening of a [`HashCommitment`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decommitment<T: Field> {
+    pub value: T,
+    pub randomness: [u8; 32],
+}
+
+impl HashCommitment {
+    /// Commits to `value` with fresh randomness.
+    pub fn commit<T: Field>(value: T) -> (Self, Decommitment<T>) {
+        let randomness: [u8; 32] = thread_rng().gen();
+        let decommitment = Decommitment { value, randomness };
+
+        (Self(digest(&decommitment)), decommitment)
+    }
+
+    /// Checks the opening and returns the committed value.
+    pub fn verify<T: Field>(&self, decommitment: &Decommitment<T>) -> Result<T, CommitError> {
+        if digest(decommitment) != self.0 {
+            return Err(CommitError::InvalidOpening);
+        }
+        Ok(decommitment.value)
+    }
+}
+
+fn digest<T: Field>(decommitment: &Decommitment<T>) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(decommitment.randomness);
+    hasher.update(decommitment.value.to_le_bytes());
+    hasher.finalize().into()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CommitError {
+    /// This role has not committed yet.
+    NotCommitted(Role),
+    /// The opening does not match the commitment.
+    InvalidOpening,
+}
+
+impl std::fmt::Display for CommitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommitError::NotCommitted(role) => write!(f, "{role:?} has not committed yet"),
+            CommitError::InvalidOpening => write!(f, "opening does not match the commitment"),
+        }
+    }
+}
+
+impl std::error::Error for CommitError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mpz_share_conversion_core::fields::{p256::P256, UniformRand};
+
+    #[test]
+    fn test_commit() {
+        let mut rng = thread_rng();
+        let sender_value = P256::rand(&mut rng);
+        let receiver_value = P256::rand(&mut rng);
+
+        let mut commit = Commit::default();
+
+        commit.commit(Role::Sender, sender_value);
+        assert_eq!(
+            commit.open(Role::Sender),
+            Err(CommitError::NotCommitted(Role::Receiver))
+        );
+
+        commit.commit(Role::Receiver, receiver_value);
+        assert_eq!(commit.open(Role::Sender), Ok(sender_value));
+        assert_eq!(commit.open(Role::Receiver), Ok(receiver_value));
+    }
+
+    #[test]
+    fn test_hash_commitment() {
+        let mut rng = thread_rng();
+        let value = P256::rand(&mut rng);
+
+        let (commitment, decommitment) = HashCommitment::commit(value);
+        assert_eq!(commitment.verify(&decommitment), Ok(value));
+
+        let changed_value = Decommitment {
+            value: value + P256::one(),
+            ..decommitment
+        };
+        assert_eq!(
+            commitment.verify(&changed_value),
+            Err(CommitError::InvalidOpening)
+        );
+
+        let changed_randomness = Decommitment {
+            randomness: [0; 32],
+            ..decommitment
+        };
+        assert_eq!(
+            commitment.verify(&changed_randomness),
+            Err(CommitError::InvalidOpening)
+        );
+    }
+}
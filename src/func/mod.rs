@@ -1,7 +1,10 @@
 //! This module implements some functionalities.
 
+pub mod commit;
 pub mod cot;
 pub mod ole;
+pub mod power_ole;
+pub mod vole;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Role {
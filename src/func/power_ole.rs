@@ -0,0 +1,162 @@
+//! This module implements an OLE functionality, which additionally checks that the inputs of both
+//! parties are powers of a nonzero element.
+//!
+//! In a real protocol this corresponds to an OLE on committed inputs together with a proof that the
+//! committed vector is a power sequence.
+
+use super::{ole::Ole, Role};
+use mpz_share_conversion_core::Field;
+
+#[derive(Debug)]
+pub struct PowerOle<T: Field> {
+    exponents: Vec<usize>,
+    inner: Ole<T>,
+    error: Option<PowerOleError>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PowerOleError {
+    /// The input of this role is not a sequence of powers of a nonzero element.
+    InvalidInput(Role),
+}
+
+impl std::fmt::Display for PowerOleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PowerOleError::InvalidInput(role) => {
+                write!(
+                    f,
+                    "{role:?} input is not a power sequence of a nonzero element"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for PowerOleError {}
+
+impl<T: Field> PowerOle<T> {
+    /// Creates a new functionality, which expects the inputs to be the powers `r^e` for the given
+    /// exponents `e`.
+    pub fn new(exponents: Vec<usize>) -> Self {
+        assert!(
+            exponents.contains(&1),
+            "exponents need to contain 1 to determine the base"
+        );
+
+        Self {
+            exponents,
+            inner: Ole::default(),
+            error: None,
+        }
+    }
+
+    pub fn input(&mut self, role: Role, input: Vec<T>) {
+        if self.error.is_none() && !is_power_sequence(&self.exponents, &input) {
+            self.error = Some(PowerOleError::InvalidInput(role));
+        }
+        self.inner.input(role, input);
+    }
+
+    pub fn output(&mut self, role: Role) -> Result<Vec<T>, PowerOleError> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        Ok(self.inner.output(role))
+    }
+}
+
+fn is_power_sequence<T: Field>(exponents: &[usize], input: &[T]) -> bool {
+    if exponents.len() != input.len() {
+        return false;
+    }
+
+    let base = exponents
+        .iter()
+        .zip(input)
+        .find_map(|(&e, &el)| (e == 1).then_some(el))
+        .expect("exponents should contain 1");
+
+    base != T::zero()
+        && exponents
+            .iter()
+            .zip(input)
+            .all(|(&e, &el)| pow(base, e) == el)
+}
+
+fn pow<T: Field>(base: T, exponent: usize) -> T {
+    let mut res = T::one();
+    let mut square = base;
+    let mut exponent = exponent;
+
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            res = res * square;
+        }
+        square = square * square;
+        exponent >>= 1;
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mpz_share_conversion_core::fields::{
+        compute_product_repeated, gf2_128::Gf2_128, UniformRand,
+    };
+    use rand::thread_rng;
+
+    #[test]
+    fn test_power_ole() {
+        let mut rng = thread_rng();
+        let exponents: Vec<usize> = (0..=5).collect();
+
+        let mut input_sender = vec![Gf2_128::one()];
+        compute_product_repeated(&mut input_sender, Gf2_128::rand(&mut rng), 5);
+        let mut input_receiver = vec![Gf2_128::one()];
+        compute_product_repeated(&mut input_receiver, Gf2_128::rand(&mut rng), 5);
+
+        let mut ole = PowerOle::new(exponents.clone());
+        ole.input(Role::Sender, input_sender.clone());
+        ole.input(Role::Receiver, input_receiver.clone());
+
+        let output_sender = ole.output(Role::Sender).unwrap();
+        let output_receiver = ole.output(Role::Receiver).unwrap();
+
+        for (((is, ir), os), or) in input_sender
+            .into_iter()
+            .zip(input_receiver.clone())
+            .zip(output_sender)
+            .zip(output_receiver)
+        {
+            assert_eq!(is * ir, os + or);
+        }
+
+        // Zero is not allowed as a base
+        let mut input_zero = vec![Gf2_128::zero(); 6];
+        input_zero[0] = Gf2_128::one();
+
+        let mut ole = PowerOle::new(exponents.clone());
+        ole.input(Role::Sender, input_zero);
+        ole.input(Role::Receiver, input_receiver.clone());
+
+        assert_eq!(
+            ole.output(Role::Receiver),
+            Err(PowerOleError::InvalidInput(Role::Sender))
+        );
+
+        // Neither is a sequence which does not consist of powers
+        let mut input_receiver_wrong = input_receiver.clone();
+        input_receiver_wrong[3] = Gf2_128::rand(&mut rng);
+
+        let mut ole = PowerOle::new(exponents);
+        ole.input(Role::Sender, input_receiver);
+        ole.input(Role::Receiver, input_receiver_wrong);
+
+        assert_eq!(
+            ole.output(Role::Sender),
+            Err(PowerOleError::InvalidInput(Role::Receiver))
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! This module implements a VOLE functionality, which generates information-theoretic MACs.
+//!
+//! The sender inputs a global key `delta` and the receiver inputs a vector `x`. The sender gets
+//! random keys `k` and the receiver gets the MACs `m = k + delta * x`. The same `delta` can be
+//! used for several inputs of the receiver.
+
+use mpz_share_conversion_core::Field;
+use rand::thread_rng;
+
+#[derive(Debug)]
+pub struct Vole<T: Field> {
+    delta: Option<T>,
+    input_receiver: Vec<T>,
+    keys: Vec<T>,
+    macs: Vec<T>,
+}
+
+impl<T: Field> Default for Vole<T> {
+    fn default() -> Self {
+        Self {
+            delta: None,
+            input_receiver: vec![],
+            keys: vec![],
+            macs: vec![],
+        }
+    }
+}
+
+impl<T: Field> Vole<T> {
+    pub fn input_delta(&mut self, delta: T) {
+        if let Some(old_delta) = self.delta {
+            assert_eq!(old_delta, delta, "delta cannot be changed");
+        }
+        self.delta = Some(delta);
+    }
+
+    pub fn input_x(&mut self, x: Vec<T>) {
+        assert!(self.input_receiver.is_empty(), "x has already been set");
+        self.input_receiver = x;
+    }
+
+    pub fn output_keys(&mut self) -> Vec<T> {
+        self.compute();
+        std::mem::take(&mut self.keys)
+    }
+
+    pub fn output_macs(&mut self) -> Vec<T> {
+        self.compute();
+        std::mem::take(&mut self.macs)
+    }
+
+    fn compute(&mut self) {
+        if self.input_receiver.is_empty() {
+            return;
+        }
+
+        let delta = self.delta.expect("delta has not been set");
+        let mut rng = thread_rng();
+
+        for x in std::mem::take(&mut self.input_receiver) {
+            let key = T::rand(&mut rng);
+            self.keys.push(key);
+            self.macs.push(key + delta * x);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mpz_share_conversion_core::fields::{p256::P256, UniformRand};
+
+    #[test]
+    fn test_vole() {
+        let mut rng = thread_rng();
+        let delta = P256::rand(&mut rng);
+
+        let mut vole = Vole::default();
+        vole.input_delta(delta);
+
+        for len in [5, 2] {
+            let x: Vec<P256> = (0..len).map(|_| P256::rand(&mut rng)).collect();
+
+            vole.input_delta(delta);
+            vole.input_x(x.clone());
+
+            let macs = vole.output_macs();
+            let keys = vole.output_keys();
+
+            assert_eq!(macs.len(), len);
+            for ((m, k), x) in macs.into_iter().zip(keys).zip(x) {
+                assert_eq!(m, k + delta * x);
+            }
+        }
+    }
+}
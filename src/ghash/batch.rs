@@ -0,0 +1,368 @@
+//! Batched GHASH for several independent keys
+//!
+//! The OLE inputs of all keys are concatenated, so the powers of every `H` are preprocessed with a
+//! single OLE invocation. Afterwards GHASH requests are served per key id.
+
+use super::{GhashHasher, Prover, Verifier};
+use crate::func::{ole::Ole, Role};
+use mpz_share_conversion_core::fields::gf2_128::Gf2_128;
+use std::collections::BTreeMap;
+
+/// Identifies a key in a batch.
+pub type KeyId = usize;
+
+/// Runs the batched GHASH protocol and returns GHASH of the blocks for every requested key.
+///
+/// Fails before the OLE if the parties do not hold the same keys with the same degrees and
+/// strategies.
+pub fn ghash_batch(
+    requests: &BTreeMap<KeyId, Vec<Gf2_128>>,
+    prover: &mut BatchProver,
+    verifier: &mut BatchVerifier,
+) -> Result<BTreeMap<KeyId, Gf2_128>, BatchError> {
+    check_keys(prover, verifier)?;
+
+    let mut ole = Ole::default();
+
+    // Preprocessing
+    prover.preprocess_ole_input(&mut ole);
+    verifier.preprocess_ole_input(&mut ole);
+
+    prover.preprocess_ole_output(&mut ole);
+    verifier.preprocess_ole_output(&mut ole);
+
+    // Handshake
+    let d1 = prover.handshake_a_open_d();
+    let d2 = verifier.handshake_a_open_d();
+    let d: BTreeMap<KeyId, Gf2_128> = d1
+        .iter()
+        .zip(d2.values())
+        .map(|((&id, &d1), &d2)| (id, d1 + d2))
+        .collect();
+
+    prover.handshake_a_set_di(&d);
+    verifier.handshake_a_set_di(&d);
+
+    prover.handshake_a_set_hi();
+    verifier.handshake_a_set_hi();
+
+    // Output
+    Ok(requests
+        .iter()
+        .map(|(&id, blocks)| {
+            let ghash1 = prover.handshake_output_ghash(id, blocks);
+            let ghash2 = verifier.handshake_output_ghash(id, blocks);
+            (id, ghash1 + ghash2)
+        })
+        .collect())
+}
+
+/// Checks that both parties hold the same key ids and that every key has the same degree and
+/// strategy on both sides, so the concatenated OLE inputs line up.
+fn check_keys(prover: &BatchProver, verifier: &BatchVerifier) -> Result<(), BatchError> {
+    if !prover.provers.keys().eq(verifier.verifiers.keys()) {
+        return Err(BatchError::MismatchedKeyIds);
+    }
+
+    for ((&id, prover), verifier) in prover.provers.iter().zip(verifier.verifiers.values()) {
+        if prover.degree() != verifier.degree() || prover.strategy() != verifier.strategy() {
+            return Err(BatchError::MismatchedKey(id));
+        }
+    }
+    Ok(())
+}
+
+/// Errors of the batched GHASH protocol.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BatchError {
+    /// The parties do not hold the same key ids.
+    MismatchedKeyIds,
+    /// The parties use a different degree or strategy for the key with this id.
+    MismatchedKey(KeyId),
+}
+
+impl std::fmt::Display for BatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BatchError::MismatchedKeyIds => write!(f, "the parties hold different key ids"),
+            BatchError::MismatchedKey(id) => {
+                write!(
+                    f,
+                    "the parties use a different degree or strategy for key id {id}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for BatchError {}
+
+/// Holds one [`Prover`] per key.
+///
+/// Both parties need to use the same key ids, because the OLE inputs are concatenated in the
+/// order of the key ids.
+#[derive(Debug)]
+pub struct BatchProver {
+    provers: BTreeMap<KeyId, Prover>,
+}
+
+impl BatchProver {
+    pub fn new(provers: BTreeMap<KeyId, Prover>) -> Self {
+        Self { provers }
+    }
+
+    pub fn preprocess_ole_input(&self, ole: &mut Ole<Gf2_128>) {
+        let input = self.provers.values().flat_map(Prover::ole_input).collect();
+        ole.input(Role::Sender, input);
+    }
+
+    pub fn preprocess_ole_output(&mut self, ole: &mut Ole<Gf2_128>) {
+        let mut output = ole.output(Role::Sender).into_iter();
+
+        for prover in self.provers.values_mut() {
+            let len = prover.strategy().ole_exponents(prover.degree()).len();
+            prover.set_r_power_shares(output.by_ref().take(len).collect());
+        }
+    }
+
+    pub fn handshake_a_open_d(&self) -> BTreeMap<KeyId, Gf2_128> {
+        self.provers
+            .iter()
+            .map(|(&id, prover)| (id, prover.handshake_a_open_d()))
+            .collect()
+    }
+
+    pub fn handshake_a_set_di(&mut self, d: &BTreeMap<KeyId, Gf2_128>) {
+        for (id, prover) in self.provers.iter_mut() {
+            prover.handshake_a_set_di(d[id]);
+        }
+    }
+
+    pub fn handshake_a_set_hi(&mut self) {
+        for prover in self.provers.values_mut() {
+            prover.handshake_a_set_hi();
+        }
+    }
+
+    /// Returns the GHASH share of the blocks under the key with the given id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no key with this id.
+    pub fn handshake_output_ghash(&self, id: KeyId, blocks: &[Gf2_128]) -> Gf2_128 {
+        self.prover(id)
+            .unwrap_or_else(|| panic!("unknown key id {id}"))
+            .handshake_output_ghash(blocks)
+    }
+
+    /// Returns a hasher for the key with the given id, see [`Prover::hasher`].
+    pub fn hasher(&self, id: KeyId) -> Option<GhashHasher<'_>> {
+        self.prover(id).map(Prover::hasher)
+    }
+
+    pub fn prover(&self, id: KeyId) -> Option<&Prover> {
+        self.provers.get(&id)
+    }
+}
+
+/// Holds one [`Verifier`] per key, see [`BatchProver`].
+#[derive(Debug)]
+pub struct BatchVerifier {
+    verifiers: BTreeMap<KeyId, Verifier>,
+}
+
+impl BatchVerifier {
+    pub fn new(verifiers: BTreeMap<KeyId, Verifier>) -> Self {
+        Self { verifiers }
+    }
+
+    pub fn preprocess_ole_input(&self, ole: &mut Ole<Gf2_128>) {
+        let input = self
+            .verifiers
+            .values()
+            .flat_map(Verifier::ole_input)
+            .collect();
+        ole.input(Role::Receiver, input);
+    }
+
+    pub fn preprocess_ole_output(&mut self, ole: &mut Ole<Gf2_128>) {
+        let mut output = ole.output(Role::Receiver).into_iter();
+
+        for verifier in self.verifiers.values_mut() {
+            let len = verifier.strategy().ole_exponents(verifier.degree()).len();
+            verifier.set_r_power_shares(output.by_ref().take(len).collect());
+        }
+    }
+
+    pub fn handshake_a_open_d(&self) -> BTreeMap<KeyId, Gf2_128> {
+        self.verifiers
+            .iter()
+            .map(|(&id, verifier)| (id, verifier.handshake_a_open_d()))
+            .collect()
+    }
+
+    pub fn handshake_a_set_di(&mut self, d: &BTreeMap<KeyId, Gf2_128>) {
+        for (id, verifier) in self.verifiers.iter_mut() {
+            verifier.handshake_a_set_di(d[id]);
+        }
+    }
+
+    pub fn handshake_a_set_hi(&mut self) {
+        for verifier in self.verifiers.values_mut() {
+            verifier.handshake_a_set_hi();
+        }
+    }
+
+    /// Returns the GHASH share of the blocks under the key with the given id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no key with this id.
+    pub fn handshake_output_ghash(&self, id: KeyId, blocks: &[Gf2_128]) -> Gf2_128 {
+        self.verifier(id)
+            .unwrap_or_else(|| panic!("unknown key id {id}"))
+            .handshake_output_ghash(blocks)
+    }
+
+    /// Returns a hasher for the key with the given id, see [`Verifier::hasher`].
+    pub fn hasher(&self, id: KeyId) -> Option<GhashHasher<'_>> {
+        self.verifier(id).map(Verifier::hasher)
+    }
+
+    pub fn verifier(&self, id: KeyId) -> Option<&Verifier> {
+        self.verifiers.get(&id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ghash::{ghash, PowerStrategy};
+    use mpz_share_conversion_core::fields::UniformRand;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_ghash_batch() {
+        let mut rng = thread_rng();
+
+        // Keys with different ids, lengths and strategies
+        let keys = [
+            (3, 10, PowerStrategy::Full),
+            (7, 1, PowerStrategy::Full),
+            (1, 33, PowerStrategy::FreeSquaring),
+        ];
+
+        let mut provers = BTreeMap::new();
+        let mut verifiers = BTreeMap::new();
+        let mut requests = BTreeMap::new();
+        let mut expected = BTreeMap::new();
+
+        for (id, block_num, strategy) in keys {
+            let blocks: Vec<Gf2_128> = (0..block_num).map(|_| Gf2_128::rand(&mut rng)).collect();
+            let h1 = Gf2_128::rand(&mut rng);
+            let h2 = Gf2_128::rand(&mut rng);
+
+            let mut prover = Prover::new_with_strategy(block_num, h1, strategy);
+            let mut verifier = Verifier::new_with_strategy(block_num, h2, strategy);
+            expected.insert(id, ghash(&blocks, &mut prover, &mut verifier));
+
+            provers.insert(id, Prover::new_with_strategy(block_num, h1, strategy));
+            verifiers.insert(id, Verifier::new_with_strategy(block_num, h2, strategy));
+            requests.insert(id, blocks);
+        }
+
+        let mut prover = BatchProver::new(provers);
+        let mut verifier = BatchVerifier::new(verifiers);
+
+        let ghashes = ghash_batch(&requests, &mut prover, &mut verifier).unwrap();
+        assert_eq!(ghashes, expected);
+
+        // A request for a single key with fewer blocks
+        let blocks = &requests[&1][..5];
+        let mut hasher1 = prover.hasher(1).unwrap();
+        let mut hasher2 = verifier.hasher(1).unwrap();
+        hasher1.update(blocks);
+        hasher2.update(blocks);
+        assert_eq!(
+            hasher1.finalize() + hasher2.finalize(),
+            prover.handshake_output_ghash(1, blocks) + verifier.handshake_output_ghash(1, blocks)
+        );
+        assert!(prover.hasher(2).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown key id 2")]
+    fn test_ghash_batch_unknown_key() {
+        let mut rng = thread_rng();
+        let blocks: Vec<Gf2_128> = (0..4).map(|_| Gf2_128::rand(&mut rng)).collect();
+
+        let mut prover = BatchProver::new(BTreeMap::from([(
+            1,
+            Prover::new(blocks.len(), Gf2_128::rand(&mut rng)),
+        )]));
+        let mut verifier = BatchVerifier::new(BTreeMap::from([(
+            1,
+            Verifier::new(blocks.len(), Gf2_128::rand(&mut rng)),
+        )]));
+
+        let _ = ghash_batch(&BTreeMap::from([(2, blocks)]), &mut prover, &mut verifier);
+    }
+
+    #[test]
+    fn test_ghash_batch_mismatched_keys() {
+        let mut rng = thread_rng();
+        let blocks: Vec<Gf2_128> = (0..4).map(|_| Gf2_128::rand(&mut rng)).collect();
+        let requests = BTreeMap::from([(1, blocks.clone())]);
+
+        // The prover holds an extra key
+        let mut prover = BatchProver::new(BTreeMap::from([
+            (1, Prover::new(blocks.len(), Gf2_128::rand(&mut rng))),
+            (2, Prover::new(blocks.len(), Gf2_128::rand(&mut rng))),
+        ]));
+        let mut verifier = BatchVerifier::new(BTreeMap::from([(
+            1,
+            Verifier::new(blocks.len(), Gf2_128::rand(&mut rng)),
+        )]));
+
+        assert_eq!(
+            ghash_batch(&requests, &mut prover, &mut verifier),
+            Err(BatchError::MismatchedKeyIds)
+        );
+
+        // The parties use different key ids
+        let mut prover = BatchProver::new(BTreeMap::from([(
+            1,
+            Prover::new(blocks.len(), Gf2_128::rand(&mut rng)),
+        )]));
+        let mut verifier = BatchVerifier::new(BTreeMap::from([(
+            2,
+            Verifier::new(blocks.len(), Gf2_128::rand(&mut rng)),
+        )]));
+
+        assert_eq!(
+            ghash_batch(&requests, &mut prover, &mut verifier),
+            Err(BatchError::MismatchedKeyIds)
+        );
+    }
+
+    #[test]
+    fn test_ghash_batch_mismatched_degree() {
+        let mut rng = thread_rng();
+        let blocks: Vec<Gf2_128> = (0..4).map(|_| Gf2_128::rand(&mut rng)).collect();
+        let requests = BTreeMap::from([(1, blocks.clone())]);
+
+        let mut prover = BatchProver::new(BTreeMap::from([
+            (1, Prover::new(blocks.len(), Gf2_128::rand(&mut rng))),
+            (2, Prover::new(8, Gf2_128::rand(&mut rng))),
+        ]));
+        let mut verifier = BatchVerifier::new(BTreeMap::from([
+            (1, Verifier::new(blocks.len(), Gf2_128::rand(&mut rng))),
+            (2, Verifier::new(6, Gf2_128::rand(&mut rng))),
+        ]));
+
+        assert_eq!(
+            ghash_batch(&requests, &mut prover, &mut verifier),
+            Err(BatchError::MismatchedKey(2))
+        );
+    }
+}
@@ -0,0 +1,138 @@
+//! Conversions between GCM blocks and field elements
+//!
+//! GCM reads the bits of a block in reflected order: the most significant bit of the first byte is
+//! the coefficient of `x^0` and the least significant bit of the last byte is the coefficient of
+//! `x^127`.
+
+use mpz_share_conversion_core::{fields::gf2_128::Gf2_128, Field};
+
+/// Maps a GCM block to its field element.
+pub fn block_to_gf2_128(block: [u8; 16]) -> Gf2_128 {
+    Gf2_128::new(u128::from_be_bytes(block).reverse_bits())
+}
+
+/// Maps a field element to its GCM block, inverting [`block_to_gf2_128`].
+///
+/// Since the map is linear, this also maps a share of a GHASH output to a share of the tag block.
+pub fn gf2_128_to_block(element: Gf2_128) -> [u8; 16] {
+    let bytes: [u8; 16] = element
+        .to_le_bytes()
+        .try_into()
+        .expect("field element should have 16 bytes");
+
+    u128::from_le_bytes(bytes).reverse_bits().to_be_bytes()
+}
+
+/// Returns the GHASH input of a GCM record in GCM order, e.g. for [`super::GhashHasher`].
+///
+/// The additional data and the ciphertext are each padded with zeros to full blocks, followed by
+/// a block with their bit lengths. There are [`gcm_block_num`] blocks.
+pub fn gcm_blocks(aad: &[u8], ciphertext: &[u8]) -> Vec<Gf2_128> {
+    let mut length_block = [0_u8; 16];
+    length_block[..8].copy_from_slice(&(aad.len() as u64 * 8).to_be_bytes());
+    length_block[8..].copy_from_slice(&(ciphertext.len() as u64 * 8).to_be_bytes());
+
+    aad.chunks(16)
+        .chain(ciphertext.chunks(16))
+        .map(|chunk| {
+            let mut block = [0_u8; 16];
+            block[..chunk.len()].copy_from_slice(chunk);
+            block
+        })
+        .chain(std::iter::once(length_block))
+        .map(block_to_gf2_128)
+        .collect()
+}
+
+/// Returns the number of GHASH blocks of a GCM record, which is needed upfront to stream it.
+pub fn gcm_block_num(aad_len: usize, ciphertext_len: usize) -> usize {
+    aad_len.div_ceil(16) + ciphertext_len.div_ceil(16) + 1
+}
+
+/// Returns the GHASH input of a GCM record in the block order of the GHASH protocol.
+///
+/// GCM multiplies the first block with the highest power of `H`, while the protocol multiplies
+/// the first block with `H`, so these are the [`gcm_blocks`] in reverse order.
+pub fn ghash_input(aad: &[u8], ciphertext: &[u8]) -> Vec<Gf2_128> {
+    let mut blocks = gcm_blocks(aad, ciphertext);
+    blocks.reverse();
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ghash::{ghash, Prover, Verifier};
+    use crate::test_utils::hex;
+    use mpz_share_conversion_core::fields::UniformRand;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_block_to_gf2_128() {
+        // The first bit of a GCM block is x^0, so this is the multiplicative identity.
+        let mut one = [0_u8; 16];
+        one[0] = 0x80;
+        assert_eq!(block_to_gf2_128(one), Gf2_128::one());
+
+        let mut x = [0_u8; 16];
+        x[0] = 0x40;
+        assert_eq!(block_to_gf2_128(x), Gf2_128::new(2));
+
+        let element = Gf2_128::rand(&mut thread_rng());
+        assert_eq!(block_to_gf2_128(gf2_128_to_block(element)), element);
+    }
+
+    #[test]
+    fn test_ghash_nist_vectors() {
+        // Test cases 2, 3 and 4 of the GCM specification by McGrew and Viega, which NIST refers to
+        let test_cases = [
+            (
+                "66e94bd4ef8a2c3b884cfa59ca342b2e",
+                "",
+                "0388dace60b6a392f328c2b971b2fe78",
+                "f38cbb1ad69223dcc3457ae5b6b0f885",
+                "58e2fccefa7e3061367f1d57a4e7455a",
+                "ab6e47d42cec13bdf53a67b21257bddf",
+            ),
+            (
+                "b83b533708bf535d0aa6e52980d53b78",
+                "",
+                "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e\
+                 21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091473f5985",
+                "7f1b32b81b820d02614f8895ac1d4eac",
+                "3247184b3c4f69a44dbcd22887bbb418",
+                "4d5c2af327cd64a62cf35abd2ba6fab4",
+            ),
+            (
+                "b83b533708bf535d0aa6e52980d53b78",
+                "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+                "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e\
+                 21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091",
+                "698e57f70e6ecc7fd9463b7260a9ae5f",
+                "3247184b3c4f69a44dbcd22887bbb418",
+                "5bc94fbc3221a5db94fae95ae7121a47",
+            ),
+        ];
+
+        for (h, aad, ciphertext, expected_ghash, ek_y0, expected_tag) in test_cases {
+            let h = block_to_gf2_128(hex(h).try_into().unwrap());
+            let blocks = ghash_input(&hex(aad), &hex(ciphertext));
+            assert_eq!(
+                blocks.len(),
+                gcm_block_num(hex(aad).len(), hex(ciphertext).len())
+            );
+
+            let h1 = Gf2_128::rand(&mut thread_rng());
+            let h2 = h + -h1;
+
+            let mut prover = Prover::new(blocks.len(), h1);
+            let mut verifier = Verifier::new(blocks.len(), h2);
+
+            let output = gf2_128_to_block(ghash(&blocks, &mut prover, &mut verifier));
+            assert_eq!(output.to_vec(), hex(expected_ghash));
+
+            let tag: Vec<u8> = output.iter().zip(hex(ek_y0)).map(|(a, b)| a ^ b).collect();
+            assert_eq!(tag, hex(expected_tag));
+        }
+    }
+}
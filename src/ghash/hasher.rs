@@ -0,0 +1,249 @@
+//! An incremental GHASH hasher for blocks which arrive in chunks
+
+use super::ghash_share;
+use mpz_share_conversion_core::{fields::gf2_128::Gf2_128, Field};
+
+/// Computes a party's GHASH share incrementally.
+///
+/// In this module block `i` is multiplied with `H^(i + 1)`, so the power of a block only depends
+/// on its position from the start and not on the total number of blocks. The hasher created by
+/// `hasher` therefore only tracks the position of the next block and does not need the length
+/// upfront. [`GhashHasher::finalize`] returns the same share as `handshake_output_ghash` for all
+/// blocks passed to [`GhashHasher::update`].
+///
+/// GCM multiplies the first block with the highest power of `H`, so the power of a block depends
+/// on the total number of blocks. The hasher created by `gcm_hasher` takes this number upfront and
+/// accepts the blocks in GCM order, see [`super::gcm_blocks`].
+#[derive(Debug, Clone)]
+pub struct GhashHasher<'a> {
+    xi: &'a [Gf2_128],
+    block_num: Option<usize>,
+    position: usize,
+    share: Gf2_128,
+}
+
+impl<'a> GhashHasher<'a> {
+    pub(crate) fn new(xi: &'a [Gf2_128]) -> Self {
+        Self {
+            xi,
+            block_num: None,
+            position: 0,
+            share: Gf2_128::zero(),
+        }
+    }
+
+    /// Creates a hasher for exactly `block_num` blocks in GCM order.
+    pub(crate) fn new_gcm(xi: &'a [Gf2_128], block_num: usize) -> Self {
+        assert!(
+            block_num <= xi.len(),
+            "shares of the powers of H are not ready for {block_num} blocks"
+        );
+
+        Self {
+            xi,
+            block_num: Some(block_num),
+            position: 0,
+            share: Gf2_128::zero(),
+        }
+    }
+
+    /// Adds the next blocks to the hash.
+    ///
+    /// # Panics
+    ///
+    /// Panics if in total more blocks are added than there are shares of powers of `H`, or than
+    /// were declared for a GCM hasher.
+    pub fn update(&mut self, blocks: &[Gf2_128]) {
+        match self.block_num {
+            None => {
+                assert!(
+                    blocks.len() <= self.xi.len() - self.position,
+                    "shares of the powers of H are not ready for {} blocks",
+                    self.position + blocks.len()
+                );
+
+                self.share = self.share + ghash_share(blocks, &self.xi[self.position..]);
+            }
+            Some(block_num) => {
+                assert!(
+                    blocks.len() <= block_num - self.position,
+                    "the hasher was created for {block_num} blocks, but got {}",
+                    self.position + blocks.len()
+                );
+
+                // Block `position + j` is multiplied with `H^(block_num - position - j)`.
+                let end = block_num - self.position;
+                let powers = self.xi[end - blocks.len()..end].iter().rev();
+
+                self.share = blocks
+                    .iter()
+                    .zip(powers)
+                    .fold(self.share, |acc, (&block, &x)| acc + block * x);
+            }
+        }
+        self.position += blocks.len();
+    }
+
+    /// Returns the number of blocks added so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the GHASH share of all added blocks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a GCM hasher did not get all declared blocks.
+    pub fn finalize(self) -> Gf2_128 {
+        if let Some(block_num) = self.block_num {
+            assert_eq!(
+                self.position, block_num,
+                "the hasher was created for {block_num} blocks"
+            );
+        }
+        self.share
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::func::ole::Ole;
+    use crate::ghash::{block_to_gf2_128, gcm_blocks, ghash, typestate, Prover, Verifier};
+    use crate::test_utils::hex;
+    use mpz_share_conversion_core::fields::UniformRand;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_ghash_hasher() {
+        let mut rng = thread_rng();
+        let blocks: Vec<Gf2_128> = (0..20).map(|_| Gf2_128::rand(&mut rng)).collect();
+
+        let h1: Gf2_128 = Gf2_128::rand(&mut rng);
+        let h2: Gf2_128 = Gf2_128::rand(&mut rng);
+
+        let mut prover = Prover::new(blocks.len(), h1);
+        let mut verifier = Verifier::new(blocks.len(), h2);
+        let expected = ghash(&blocks, &mut prover, &mut verifier);
+
+        let mut prover_hasher = prover.hasher();
+        let mut verifier_hasher = verifier.hasher();
+
+        for chunk in [&blocks[..3], &blocks[3..3], &blocks[3..11], &blocks[11..]] {
+            prover_hasher.update(chunk);
+            verifier_hasher.update(chunk);
+        }
+        assert_eq!(prover_hasher.position(), blocks.len());
+
+        // The one-shot call of each party must match its hasher as well.
+        let prover_share = prover_hasher.finalize();
+        let verifier_share = verifier_hasher.finalize();
+        assert_eq!(prover_share, prover.handshake_output_ghash(&blocks));
+        assert_eq!(verifier_share, verifier.handshake_output_ghash(&blocks));
+
+        assert_eq!(prover_share + verifier_share, expected);
+    }
+
+    #[test]
+    fn test_ghash_hasher_typestate() {
+        let mut rng = thread_rng();
+        let blocks: Vec<Gf2_128> = (0..10).map(|_| Gf2_128::rand(&mut rng)).collect();
+
+        let h1: Gf2_128 = Gf2_128::rand(&mut rng);
+        let h2: Gf2_128 = Gf2_128::rand(&mut rng);
+
+        let mut prover = Prover::new(blocks.len(), h1);
+        let mut verifier = Verifier::new(blocks.len(), h2);
+        let expected = ghash(&blocks, &mut prover, &mut verifier);
+
+        let prover = typestate::Prover::new(blocks.len(), h1);
+        let verifier = typestate::Verifier::new(blocks.len(), h2);
+        let mut ole = Ole::default();
+        prover.preprocess_ole_input(&mut ole);
+        verifier.preprocess_ole_input(&mut ole);
+        let prover = prover.preprocess_ole_output(&mut ole);
+        let verifier = verifier.preprocess_ole_output(&mut ole);
+
+        let d = prover.handshake_a_open_d() + verifier.handshake_a_open_d();
+        let prover = prover.handshake_a_set_di(d);
+        let verifier = verifier.handshake_a_set_di(d);
+
+        let mut prover_hasher = prover.hasher();
+        let mut verifier_hasher = verifier.hasher();
+        for chunk in blocks.chunks(3) {
+            prover_hasher.update(chunk);
+            verifier_hasher.update(chunk);
+        }
+
+        assert_eq!(
+            prover_hasher.finalize() + verifier_hasher.finalize(),
+            expected
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "not ready for 11 blocks")]
+    fn test_ghash_hasher_too_many_blocks() {
+        let mut rng = thread_rng();
+        let blocks: Vec<Gf2_128> = (0..11).map(|_| Gf2_128::rand(&mut rng)).collect();
+
+        let mut prover = Prover::new(10, Gf2_128::rand(&mut rng));
+        let mut verifier = Verifier::new(10, Gf2_128::rand(&mut rng));
+        let _ = ghash(&blocks[..10], &mut prover, &mut verifier);
+
+        let mut hasher = prover.hasher();
+        hasher.update(&blocks[..6]);
+        hasher.update(&blocks[6..]);
+    }
+
+    #[test]
+    fn test_ghash_gcm_hasher() {
+        // Test case 4 of the GCM specification by McGrew and Viega, which NIST refers to
+        let h = block_to_gf2_128(hex("b83b533708bf535d0aa6e52980d53b78").try_into().unwrap());
+        let aad = hex("feedfacedeadbeeffeedfacedeadbeefabaddad2");
+        let ciphertext = hex(
+            "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e\
+             21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091",
+        );
+        let expected =
+            block_to_gf2_128(hex("698e57f70e6ecc7fd9463b7260a9ae5f").try_into().unwrap());
+
+        let blocks = gcm_blocks(&aad, &ciphertext);
+        let h1 = Gf2_128::rand(&mut thread_rng());
+        let h2 = h + -h1;
+
+        // The parties need the powers of H up to the record length, but more do not hurt.
+        let mut prover = Prover::new(blocks.len() + 3, h1);
+        let mut verifier = Verifier::new(blocks.len() + 3, h2);
+        let _ = ghash(&[], &mut prover, &mut verifier);
+
+        let mut prover_hasher = prover.gcm_hasher(blocks.len());
+        let mut verifier_hasher = verifier.gcm_hasher(blocks.len());
+
+        // The blocks are streamed in GCM order: additional data, ciphertext, lengths
+        for chunk in [&blocks[..2], &blocks[2..2], &blocks[2..5], &blocks[5..]] {
+            prover_hasher.update(chunk);
+            verifier_hasher.update(chunk);
+        }
+
+        assert_eq!(
+            prover_hasher.finalize() + verifier_hasher.finalize(),
+            expected
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "the hasher was created for 4 blocks")]
+    fn test_ghash_gcm_hasher_missing_blocks() {
+        let mut rng = thread_rng();
+        let blocks: Vec<Gf2_128> = (0..3).map(|_| Gf2_128::rand(&mut rng)).collect();
+
+        let mut prover = Prover::new(4, Gf2_128::rand(&mut rng));
+        let mut verifier = Verifier::new(4, Gf2_128::rand(&mut rng));
+        let _ = ghash(&blocks, &mut prover, &mut verifier);
+
+        let mut hasher = prover.gcm_hasher(4);
+        hasher.update(&blocks);
+        let _ = hasher.finalize();
+    }
+}
@@ -1,91 +1,341 @@
 //! This module is a testing ground for the GHASH protocol (page 36) from <https://eprint.iacr.org/2023/964>
+//!
+//! See [`typestate`] for an API which enforces the order of the protocol steps.
 
+pub mod batch;
+mod block;
+mod hasher;
 mod prover;
+pub mod typestate;
 mod verifier;
 
-use crate::func::ole::Ole;
+use crate::func::{
+    commit::{Decommitment, HashCommitment},
+    ole::Ole,
+    power_ole::{PowerOle, PowerOleError},
+    Role,
+};
+pub use crate::polyeval::PowerStrategy;
+pub use block::{block_to_gf2_128, gcm_block_num, gcm_blocks, gf2_128_to_block, ghash_input};
+pub use hasher::GhashHasher;
 use mpz_share_conversion_core::{fields::gf2_128::Gf2_128, Field};
 pub use prover::{MaliciousProver, Prover};
-pub use verifier::Verifier;
+pub use verifier::{MaliciousVerifier, Verifier, VerifierDeviation};
+
+/// A party of the GHASH protocol.
+///
+/// The drivers of this module are generic over the parties, so a malicious party only replaces the
+/// steps in which it deviates from the protocol.
+pub trait Party {
+    /// Returns the exponents of `r` whose shares are computed via OLE.
+    fn ole_exponents(&self) -> Vec<usize>;
+    fn preprocess_ole_input(&self, ole: &mut Ole<Gf2_128>);
+    fn preprocess_ole_output(&mut self, ole: &mut Ole<Gf2_128>);
+    fn preprocess_power_ole_input(&self, ole: &mut PowerOle<Gf2_128>);
+    fn preprocess_power_ole_output(
+        &mut self,
+        ole: &mut PowerOle<Gf2_128>,
+    ) -> Result<(), PowerOleError>;
+    fn handshake_a_open_d(&self) -> Gf2_128;
+    /// Opens the committed share of `d`, after seeing the opened share `d_other` of the other
+    /// party.
+    fn handshake_a_open_committed_d(
+        &self,
+        decommitment: Decommitment<Gf2_128>,
+        _d_other: Gf2_128,
+    ) -> Decommitment<Gf2_128> {
+        decommitment
+    }
+    fn handshake_a_set_di(&mut self, d: Gf2_128);
+    fn handshake_a_set_hi(&mut self);
+    fn handshake_output_ghash(&self, blocks: &[Gf2_128]) -> Gf2_128;
+}
+
+/// Implements [`Party`] with the inherent methods of the party, where `$inner` is the honest party
+/// it wraps.
+macro_rules! impl_party {
+    ($party:ty, |$this:ident| $inner:expr $(, $method:item)*) => {
+        impl Party for $party {
+            $($method)*
+
+            fn ole_exponents(&self) -> Vec<usize> {
+                let $this = self;
+                $inner.strategy().ole_exponents($inner.degree())
+            }
+
+            fn preprocess_ole_input(&self, ole: &mut Ole<Gf2_128>) {
+                self.preprocess_ole_input(ole)
+            }
+
+            fn preprocess_ole_output(&mut self, ole: &mut Ole<Gf2_128>) {
+                self.preprocess_ole_output(ole)
+            }
+
+            fn preprocess_power_ole_input(&self, ole: &mut PowerOle<Gf2_128>) {
+                self.preprocess_power_ole_input(ole)
+            }
+
+            fn preprocess_power_ole_output(
+                &mut self,
+                ole: &mut PowerOle<Gf2_128>,
+            ) -> Result<(), PowerOleError> {
+                self.preprocess_power_ole_output(ole)
+            }
+
+            fn handshake_a_open_d(&self) -> Gf2_128 {
+                self.handshake_a_open_d()
+            }
+
+            fn handshake_a_set_di(&mut self, d: Gf2_128) {
+                self.handshake_a_set_di(d)
+            }
+
+            fn handshake_a_set_hi(&mut self) {
+                self.handshake_a_set_hi()
+            }
+
+            fn handshake_output_ghash(&self, blocks: &[Gf2_128]) -> Gf2_128 {
+                self.handshake_output_ghash(blocks)
+            }
+        }
+    };
+}
+
+impl_party!(Prover, |prover| prover);
+impl_party!(Verifier, |verifier| verifier);
+impl_party!(MaliciousProver, |prover| prover.inner);
+impl_party!(
+    MaliciousVerifier,
+    |verifier| verifier.inner,
+    fn handshake_a_open_committed_d(
+        &self,
+        decommitment: Decommitment<Gf2_128>,
+        d_other: Gf2_128,
+    ) -> Decommitment<Gf2_128> {
+        self.handshake_a_open_committed_d(decommitment, d_other)
+    }
+);
+
+/// The options of the GHASH protocol.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GhashOptions {
+    /// Aborts if one of the parties does not use the powers of a nonzero element as OLE input.
+    ///
+    /// This prevents the attack where a party sets its `r` to zero and learns `H` from `d`.
+    pub hardened: bool,
+    /// Both parties commit to their shares of `d` before opening them.
+    ///
+    /// Without the commitments a rushing party can wait for the other share and then choose its
+    /// own share, which lets it pick `d`.
+    pub commit_d: bool,
+}
 
 pub fn ghash(blocks: &[Gf2_128], prover: &mut Prover, verifier: &mut Verifier) -> Gf2_128 {
-    let mut ole = Ole::default();
+    ghash_with(blocks, prover, verifier, GhashOptions::default())
+        .expect("the OLE does not check the inputs")
+}
+
+/// A hardened version of [`ghash`], see [`GhashOptions::hardened`].
+pub fn ghash_hardened(
+    blocks: &[Gf2_128],
+    prover: &mut Prover,
+    verifier: &mut Verifier,
+) -> Result<Gf2_128, GhashError> {
+    let options = GhashOptions {
+        hardened: true,
+        ..Default::default()
+    };
+    ghash_with(blocks, prover, verifier, options)
+}
+
+/// Runs the GHASH protocol with the given options between parties, which may be malicious.
+pub fn ghash_with(
+    blocks: &[Gf2_128],
+    prover: &mut impl Party,
+    verifier: &mut impl Party,
+    options: GhashOptions,
+) -> Result<Gf2_128, GhashError> {
+    preprocess(prover, verifier, options)?;
+
+    let d = if options.commit_d {
+        open_committed_d(prover, verifier)?
+    } else {
+        prover.handshake_a_open_d() + verifier.handshake_a_open_d()
+    };
+    set_d(prover, verifier, d);
+
+    let ghash1 = prover.handshake_output_ghash(blocks);
+    let ghash2 = verifier.handshake_output_ghash(blocks);
 
-    prover.preprocess_ole_input(&mut ole);
-    verifier.preprocess_ole_input(&mut ole);
+    Ok(ghash1 + ghash2)
+}
+
+/// Computes GHASH of blocks, which are additively shared between prover and verifier.
+///
+/// The blocks are `prover_blocks[i] + verifier_blocks[i]`, so a public block or a block known to
+/// only one party can be passed as the block itself and zero.
+pub fn ghash_shared_blocks(
+    prover_blocks: &[Gf2_128],
+    verifier_blocks: &[Gf2_128],
+    prover: &mut Prover,
+    verifier: &mut Verifier,
+) -> Gf2_128 {
+    assert_eq!(prover_blocks.len(), verifier_blocks.len());
 
-    prover.preprocess_ole_output(&mut ole);
-    verifier.preprocess_ole_output(&mut ole);
+    preprocess(prover, verifier, GhashOptions::default())
+        .expect("the OLE does not check the inputs");
 
     let d1 = prover.handshake_a_open_d();
     let d2 = verifier.handshake_a_open_d();
-    let d = d1 + d2;
+    set_d(prover, verifier, d1 + d2);
 
-    prover.handshake_a_set_di(d);
-    verifier.handshake_a_set_di(d);
+    let mut ole = Ole::default();
 
-    prover.handshake_a_set_hi();
-    verifier.handshake_a_set_hi();
+    prover.handshake_shared_ole_input(prover_blocks, &mut ole);
+    verifier.handshake_shared_ole_input(verifier_blocks, &mut ole);
 
-    let ghash1 = prover.handshake_output_ghash(blocks);
-    let ghash2 = verifier.handshake_output_ghash(blocks);
+    let ghash1 = prover.handshake_output_ghash_shared(prover_blocks, &mut ole);
+    let ghash2 = verifier.handshake_output_ghash_shared(verifier_blocks, &mut ole);
 
     ghash1 + ghash2
 }
 
-/// The prover is malicious and will set r1 to zero
+/// The prover is malicious and uses its chosen OLE input, e.g. it sets r1 to zero
 pub fn ghash_with_input_zero_from_prover(
     blocks: &[Gf2_128],
     prover: &mut MaliciousProver,
     verifier: &mut Verifier,
 ) -> Gf2_128 {
-    let mut ole = Ole::default();
+    ghash_with(blocks, prover, verifier, GhashOptions::default())
+        .expect("the OLE does not check the inputs")
+}
 
-    prover.preprocess_ole_input(&mut ole);
-    verifier.preprocess_ole_input(&mut ole);
+/// Computes the shares of the powers of the masks with an OLE, which checks the inputs if the
+/// protocol is hardened.
+fn preprocess(
+    prover: &mut impl Party,
+    verifier: &mut impl Party,
+    options: GhashOptions,
+) -> Result<(), GhashError> {
+    if options.hardened {
+        let mut ole = PowerOle::new(prover.ole_exponents());
+
+        prover.preprocess_power_ole_input(&mut ole);
+        verifier.preprocess_power_ole_input(&mut ole);
+
+        prover.preprocess_power_ole_output(&mut ole)?;
+        verifier.preprocess_power_ole_output(&mut ole)?;
+    } else {
+        let mut ole = Ole::default();
+
+        prover.preprocess_ole_input(&mut ole);
+        verifier.preprocess_ole_input(&mut ole);
+
+        prover.preprocess_ole_output(&mut ole);
+        verifier.preprocess_ole_output(&mut ole);
+    }
+    Ok(())
+}
 
-    prover.preprocess_ole_output(&mut ole);
-    verifier.preprocess_ole_output(&mut ole);
+/// Opens `d`, where both parties exchange the commitments to their shares first and then the
+/// openings, which the other party checks.
+///
+/// The prover opens first, so the verifier is the party which could rush.
+fn open_committed_d(prover: &impl Party, verifier: &impl Party) -> Result<Gf2_128, GhashError> {
+    let (commitment1, decommitment1) = HashCommitment::commit(prover.handshake_a_open_d());
+    let (commitment2, decommitment2) = HashCommitment::commit(verifier.handshake_a_open_d());
 
-    let d1 = prover.handshake_a_open_d();
-    let d2 = verifier.handshake_a_open_d();
-    let d = d1 + d2;
+    let d1 = verify_opening(Role::Sender, &commitment1, &decommitment1)?;
+    let decommitment2 = verifier.handshake_a_open_committed_d(decommitment2, d1);
+    let d2 = verify_opening(Role::Receiver, &commitment2, &decommitment2)?;
 
+    Ok(d1 + d2)
+}
+
+/// Sets the opened `d` and computes the shares of the powers of `H`.
+fn set_d(prover: &mut impl Party, verifier: &mut impl Party, d: Gf2_128) {
     prover.handshake_a_set_di(d);
     verifier.handshake_a_set_di(d);
 
     prover.handshake_a_set_hi();
     verifier.handshake_a_set_hi();
+}
 
-    let ghash1 = prover.handshake_output_ghash(blocks);
-    let ghash2 = verifier.handshake_output_ghash(blocks);
+/// Errors which abort the GHASH protocol.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GhashError {
+    /// The OLE inputs of a party are not the powers of a nonzero element.
+    InvalidOleInput(Role),
+    /// The opening of a committed share of `d` does not match the commitment.
+    InvalidOpening(Role),
+}
 
-    ghash1 + ghash2
+impl std::fmt::Display for GhashError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GhashError::InvalidOleInput(role) => write!(f, "invalid OLE input from {role:?}"),
+            GhashError::InvalidOpening(role) => write!(f, "invalid opening of d from {role:?}"),
+        }
+    }
 }
 
-fn pascal_tri<T: Field>(n: usize) -> Vec<Vec<T>> {
-    let mut pascal = vec![vec![T::one()]];
+impl std::error::Error for GhashError {}
 
-    for _ in 0..n {
-        let last_row = pascal.last().unwrap();
-        let mut new_row = vec![T::one()];
+impl From<PowerOleError> for GhashError {
+    fn from(err: PowerOleError) -> Self {
+        match err {
+            PowerOleError::InvalidInput(role) => GhashError::InvalidOleInput(role),
+        }
+    }
+}
 
-        last_row
+/// Checks the opening of the share of `d` committed by `role`.
+fn verify_opening(
+    role: Role,
+    commitment: &HashCommitment,
+    decommitment: &Decommitment<Gf2_128>,
+) -> Result<Gf2_128, GhashError> {
+    commitment
+        .verify(decommitment)
+        .map_err(|_| GhashError::InvalidOpening(role))
+}
+
+/// Returns the share of GHASH for the given blocks and shares of the powers of H.
+///
+/// With the `rayon` feature the sum is computed in parallel. Addition in GF(2^128) is exact, so the
+/// result does not depend on the order of the summation.
+pub(crate) fn ghash_share(blocks: &[Gf2_128], xi: &[Gf2_128]) -> Gf2_128 {
+    assert!(
+        blocks.len() <= xi.len(),
+        "shares of the powers of H are not ready for {} blocks",
+        blocks.len()
+    );
+
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        blocks
+            .par_iter()
+            .zip(xi)
+            .map(|(&block, &x)| block * x)
+            .reduce(Gf2_128::zero, |a, b| a + b)
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+        blocks
             .iter()
-            .map_windows(|[&a, &b]| a + b)
-            .for_each(|el| {
-                new_row.push(el);
-            });
-        new_row.push(T::one());
-        pascal.push(new_row);
+            .zip(xi)
+            .fold(Gf2_128::zero(), |acc, (&block, &x)| acc + block * x)
     }
-    pascal
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use mpz_share_conversion_core::fields::{compute_product_repeated, UniformRand};
+    use mpz_share_conversion_core::{
+        fields::{compute_product_repeated, UniformRand},
+        Field,
+    };
     use rand::thread_rng;
 
     #[test]
@@ -120,23 +370,353 @@ mod tests {
         let mut rng = thread_rng();
         let blocks: Vec<Gf2_128> = (0..10).map(|_| Gf2_128::rand(&mut rng)).collect();
 
+        let h1: Gf2_128 = Gf2_128::rand(&mut rng);
+        let h2: Gf2_128 = Gf2_128::rand(&mut rng);
+
+        let mut prover = MaliciousProver::new(blocks.len(), h1);
+        let mut verifier = Verifier::new(blocks.len(), h2);
+
+        let options = GhashOptions {
+            hardened: true,
+            ..Default::default()
+        };
+        let result = ghash_with(&blocks, &mut prover, &mut verifier, options);
+
+        // The protocol aborts before d is opened, so the prover learns nothing about the MAC key
+        assert_eq!(result, Err(GhashError::InvalidOleInput(Role::Sender)));
+        assert!(prover.inner.d_powers().is_empty());
+    }
+
+    #[test]
+    fn test_ghash_hardened() {
+        let mut rng = thread_rng();
+        let blocks: Vec<Gf2_128> = (0..10).map(|_| Gf2_128::rand(&mut rng)).collect();
+
+        let h1: Gf2_128 = Gf2_128::rand(&mut rng);
+        let h2: Gf2_128 = Gf2_128::rand(&mut rng);
+
+        for strategy in [PowerStrategy::Full, PowerStrategy::FreeSquaring] {
+            let mut prover = Prover::new_with_strategy(blocks.len(), h1, strategy);
+            let mut verifier = Verifier::new_with_strategy(blocks.len(), h2, strategy);
+            let ghash_hardened = ghash_hardened(&blocks, &mut prover, &mut verifier).unwrap();
+
+            let mut prover = Prover::new_with_strategy(blocks.len(), h1, strategy);
+            let mut verifier = Verifier::new_with_strategy(blocks.len(), h2, strategy);
+            let ghash = ghash(&blocks, &mut prover, &mut verifier);
+
+            assert_eq!(ghash_hardened, ghash);
+        }
+    }
+
+    #[test]
+    fn test_malicious_prover_known_r1() {
+        let mut rng = thread_rng();
+        let blocks: Vec<Gf2_128> = (0..10).map(|_| Gf2_128::rand(&mut rng)).collect();
+
         let h1: Gf2_128 = Gf2_128::rand(&mut rng);
         let h2: Gf2_128 = Gf2_128::rand(&mut rng);
         let h = h1 + h2;
 
+        // r1 = 0 leaks h and all powers
         let mut prover = MaliciousProver::new(blocks.len(), h1);
         let mut verifier = Verifier::new(blocks.len(), h2);
+        let _ = ghash_with_input_zero_from_prover(&blocks, &mut prover, &mut verifier);
+
+        let mut hi = vec![h];
+        compute_product_repeated(&mut hi, h, blocks.len() - 1);
+
+        assert_eq!(prover.derive_h(), Some(h));
+        assert_eq!(
+            prover.derive_h_powers(),
+            hi.into_iter().map(Some).collect::<Vec<_>>()
+        );
+
+        // A known nonzero r1 does not leak h from the view alone. Together with the output, r2 and
+        // thus h are roots of a known polynomial of degree `blocks.len()`, which can be found with
+        // root-finding in GF(2^128). `derive_h_from_output` only solves the linear case.
+        let r1 = Gf2_128::rand(&mut rng);
+        let mut prover = MaliciousProver::with_r1(blocks.len(), h1, r1);
+        let mut verifier = Verifier::new(blocks.len(), h2);
+        let ghash = ghash_with_input_zero_from_prover(&blocks, &mut prover, &mut verifier);
+
+        assert_eq!(ghash, ghash_reference(&blocks, h));
+        assert_eq!(prover.derive_h(), None);
+        assert!(prover.derive_h_powers().iter().all(Option::is_none));
+        assert_eq!(prover.derive_h_from_output(&blocks, ghash), None);
+    }
+
+    #[test]
+    fn test_malicious_prover_non_geometric_input() {
+        let mut rng = thread_rng();
+        let blocks: Vec<Gf2_128> = (0..10).map(|_| Gf2_128::rand(&mut rng)).collect();
+
+        let h1: Gf2_128 = Gf2_128::rand(&mut rng);
+        let h2: Gf2_128 = Gf2_128::rand(&mut rng);
+
+        let ole_input: Vec<Gf2_128> = (0..=blocks.len())
+            .map(|_| Gf2_128::rand(&mut rng))
+            .collect();
+
+        let mut prover = MaliciousProver::with_ole_input(blocks.len(), h1, ole_input);
+        let mut verifier = Verifier::new(blocks.len(), h2);
+        let ghash = ghash_with_input_zero_from_prover(&blocks, &mut prover, &mut verifier);
+
+        // The output is wrong, and as long as the input for r^1 is nonzero h does not leak from
+        // the view alone. The output is again a known polynomial in r2 of higher degree.
+        assert_ne!(ghash, ghash_reference(&blocks, h1 + h2));
+        assert_eq!(prover.derive_h(), None);
+        assert!(prover.derive_h_powers().iter().all(Option::is_none));
+        assert_eq!(prover.derive_h_from_output(&blocks, ghash), None);
+    }
+
+    #[test]
+    fn test_malicious_prover_zeroed_powers() {
+        let mut rng = thread_rng();
+        let blocks: Vec<Gf2_128> = (0..10).map(|_| Gf2_128::rand(&mut rng)).collect();
+
+        let h1: Gf2_128 = Gf2_128::rand(&mut rng);
+        let h2: Gf2_128 = Gf2_128::rand(&mut rng);
+        let h = h1 + h2;
+
+        // Zeroing r^2 fixes the sum of the shares of h^2 to d^2, which the prover knows
+        let mut prover = MaliciousProver::with_zeroed_powers(blocks.len(), h1, &[2]);
+        let mut verifier = Verifier::new(blocks.len(), h2);
+        let _ = ghash_with_input_zero_from_prover(&blocks, &mut prover, &mut verifier);
+
+        let d = prover.inner.d_powers()[1];
+        let h_powers = prover.derive_h_powers();
+
+        assert_eq!(prover.derive_h(), None);
+        assert_eq!(h_powers[1], Some(d * d));
+        assert_eq!(h_powers.iter().filter(|h| h.is_some()).count(), 1);
+
+        // Zeroing all powers except r^0 and r^1 makes the output linear in r2, so h leaks as soon
+        // as the prover learns the GHASH output
+        let zeroed: Vec<usize> = (2..=blocks.len()).collect();
+        let mut prover = MaliciousProver::with_zeroed_powers(blocks.len(), h1, &zeroed);
+        let mut verifier = Verifier::new(blocks.len(), h2);
+        let ghash = ghash_with_input_zero_from_prover(&blocks, &mut prover, &mut verifier);
+
+        assert_eq!(prover.derive_h(), None);
+        assert_eq!(prover.derive_h_from_output(&blocks, ghash), Some(h));
+    }
+
+    #[test]
+    fn test_malicious_verifier_zero_input() {
+        let mut rng = thread_rng();
+        let blocks: Vec<Gf2_128> = (0..10).map(|_| Gf2_128::rand(&mut rng)).collect();
+
+        let h1: Gf2_128 = Gf2_128::rand(&mut rng);
+        let h2: Gf2_128 = Gf2_128::rand(&mut rng);
 
-        let _ghash = ghash_with_input_zero_from_prover(&blocks, &mut prover, &mut verifier);
+        let mut prover = Prover::new(blocks.len(), h1);
+        let mut verifier = MaliciousVerifier::new(blocks.len(), h2, VerifierDeviation::ZeroInput);
+
+        let ghash =
+            ghash_with(&blocks, &mut prover, &mut verifier, GhashOptions::default()).unwrap();
+
+        // The output is still correct, but the verifier learns the prover's share of the MAC key
+        assert_eq!(ghash, ghash_reference(&blocks, h1 + h2));
+        assert_eq!(verifier.inner.ready().state.d_powers[1] + -h2, h1);
+
+        // The hardened protocol aborts
+        let mut prover = Prover::new(blocks.len(), h1);
+        let mut verifier = MaliciousVerifier::new(blocks.len(), h2, VerifierDeviation::ZeroInput);
+
+        let result = ghash_with(
+            &blocks,
+            &mut prover,
+            &mut verifier,
+            GhashOptions {
+                hardened: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(result, Err(GhashError::InvalidOleInput(Role::Receiver)));
+    }
+
+    #[test]
+    fn test_malicious_verifier_non_power_input() {
+        let mut rng = thread_rng();
+        let blocks: Vec<Gf2_128> = (0..10).map(|_| Gf2_128::rand(&mut rng)).collect();
+
+        let h1: Gf2_128 = Gf2_128::rand(&mut rng);
+        let h2: Gf2_128 = Gf2_128::rand(&mut rng);
+        let k = 2;
+        let t = Gf2_128::rand(&mut rng);
+
+        let mut prover = Prover::new(blocks.len(), h1);
+        let mut verifier =
+            MaliciousVerifier::new(blocks.len(), h2, VerifierDeviation::NonPowerInput { k, t });
+
+        let ghash =
+            ghash_with(&blocks, &mut prover, &mut verifier, GhashOptions::default()).unwrap();
+
+        // The shares of r^2 now add up to r1^2 * t, so every power h^n with C(n, 2) = 1 mod 2 is
+        // shifted by d^(n - 2) * r1^2 * (t - r2^2). The verifier does not learn h1 from its view,
+        // but the error depends on the unknown r1, so it cannot predict the corrupted output.
+        let r1 = prover.ready().r1;
+        let r2 = verifier.inner.ready().r2;
+        let d_powers = prover.d_powers();
+        let error = blocks
+            .iter()
+            .enumerate()
+            .map(|(i, &block)| (i + 1, block))
+            .filter(|&(n, _)| n & k == k)
+            .fold(Gf2_128::zero(), |acc, (n, block)| {
+                acc + block * d_powers[n - k] * r1 * r1 * (t + -(r2 * r2))
+            });
+
+        assert_eq!(ghash, ghash_reference(&blocks, h1 + h2) + error);
+
+        // The hardened protocol aborts
+        let mut prover = Prover::new(blocks.len(), h1);
+        let mut verifier =
+            MaliciousVerifier::new(blocks.len(), h2, VerifierDeviation::NonPowerInput { k, t });
+
+        let result = ghash_with(
+            &blocks,
+            &mut prover,
+            &mut verifier,
+            GhashOptions {
+                hardened: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(result, Err(GhashError::InvalidOleInput(Role::Receiver)));
+    }
+
+    #[test]
+    fn test_malicious_verifier_wrong_d_share() {
+        let mut rng = thread_rng();
+        let blocks: Vec<Gf2_128> = (0..10).map(|_| Gf2_128::rand(&mut rng)).collect();
+
+        let h1: Gf2_128 = Gf2_128::rand(&mut rng);
+        let h2: Gf2_128 = Gf2_128::rand(&mut rng);
+        let e = Gf2_128::rand(&mut rng);
 
-        // Now we can extract the full MAC key from the prover's view
-        assert_eq!(prover.inner.d_powers[1], h);
+        let mut prover = Prover::new(blocks.len(), h1);
+        let mut verifier =
+            MaliciousVerifier::new(blocks.len(), h2, VerifierDeviation::WrongDShare(e));
+
+        let ghash =
+            ghash_with(&blocks, &mut prover, &mut verifier, GhashOptions::default()).unwrap();
+
+        // Both parties expand (d + e + r)^n, so the GHASH is computed under the key h + e. The
+        // verifier learns nothing about h1, and this is not caught by the hardened protocol.
+        assert_eq!(ghash, ghash_reference(&blocks, h1 + h2 + e));
+
+        let mut prover = Prover::new(blocks.len(), h1);
+        let mut verifier =
+            MaliciousVerifier::new(blocks.len(), h2, VerifierDeviation::WrongDShare(e));
+
+        let ghash = ghash_with(
+            &blocks,
+            &mut prover,
+            &mut verifier,
+            GhashOptions {
+                hardened: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(ghash, Ok(ghash_reference(&blocks, h1 + h2 + e)));
+    }
+
+    #[test]
+    fn test_malicious_verifier_inconsistent_hi() {
+        let mut rng = thread_rng();
+        let blocks: Vec<Gf2_128> = (0..10).map(|_| Gf2_128::rand(&mut rng)).collect();
+
+        let h1: Gf2_128 = Gf2_128::rand(&mut rng);
+        let h2: Gf2_128 = Gf2_128::rand(&mut rng);
+        let e: Vec<Gf2_128> = (0..blocks.len()).map(|_| Gf2_128::rand(&mut rng)).collect();
+
+        let mut prover = Prover::new(blocks.len(), h1);
+        let mut verifier = MaliciousVerifier::new(
+            blocks.len(),
+            h2,
+            VerifierDeviation::InconsistentHi(e.clone()),
+        );
+
+        let ghash =
+            ghash_with(&blocks, &mut prover, &mut verifier, GhashOptions::default()).unwrap();
+
+        // The output is shifted by the known error sum(b_i * e_i), which the verifier fully
+        // controls. It learns nothing about h1.
+        let error = blocks
+            .iter()
+            .zip(e.iter())
+            .fold(Gf2_128::zero(), |acc, (&b, &ei)| acc + b * ei);
+
+        assert_eq!(ghash, ghash_reference(&blocks, h1 + h2) + error);
     }
 
     #[test]
     fn test_ghash_invariants() {
         let mut rng = thread_rng();
-        let blocks: Vec<Gf2_128> = (0..1).map(|_| Gf2_128::rand(&mut rng)).collect();
+
+        let h1: Gf2_128 = Gf2_128::rand(&mut rng);
+        let h2: Gf2_128 = Gf2_128::rand(&mut rng);
+
+        let prover = typestate::Prover::new(1, h1);
+        let verifier = typestate::Verifier::new(1, h2);
+
+        let mut ole = Ole::default();
+        prover.preprocess_ole_input(&mut ole);
+        verifier.preprocess_ole_input(&mut ole);
+        let prover = prover.preprocess_ole_output(&mut ole);
+        let verifier = verifier.preprocess_ole_output(&mut ole);
+
+        let ai = prover.state.r_power_shares.clone();
+        let bi = verifier.state.r_power_shares.clone();
+        assert_eq!(ai[1] + bi[1], prover.r1 * verifier.r2);
+
+        let d = prover.handshake_a_open_d() + verifier.handshake_a_open_d();
+        let prover = prover.handshake_a_set_di(d);
+        let verifier = verifier.handshake_a_set_di(d);
+
+        assert_eq!(prover.state.d_powers[0], Gf2_128::one());
+        assert_eq!(verifier.state.d_powers[0], Gf2_128::one());
+        assert_eq!(prover.state.d_powers[1], verifier.state.d_powers[1]);
+
+        assert_eq!(
+            prover.x1 + verifier.x2,
+            prover.state.xi[0] + verifier.state.xi[0]
+        );
+        assert_eq!(prover.state.d_powers[1] + ai[1] + bi[1], h1 + h2);
+    }
+
+    #[test]
+    fn test_ghash_free_squaring() {
+        let mut rng = thread_rng();
+        let blocks: Vec<Gf2_128> = (0..33).map(|_| Gf2_128::rand(&mut rng)).collect();
+
+        let h1: Gf2_128 = Gf2_128::rand(&mut rng);
+        let h2: Gf2_128 = Gf2_128::rand(&mut rng);
+
+        let mut prover = Prover::new(blocks.len(), h1);
+        let mut verifier = Verifier::new(blocks.len(), h2);
+        let ghash_full = ghash(&blocks, &mut prover, &mut verifier);
+
+        let mut prover = Prover::new_with_strategy(blocks.len(), h1, PowerStrategy::FreeSquaring);
+        let mut verifier =
+            Verifier::new_with_strategy(blocks.len(), h2, PowerStrategy::FreeSquaring);
+        let ghash_free_squaring = ghash(&blocks, &mut prover, &mut verifier);
+
+        assert_eq!(ghash_full, ghash_free_squaring);
+        assert_eq!(prover.ready().state.xi.len(), blocks.len());
+        assert_eq!(
+            PowerStrategy::FreeSquaring
+                .ole_exponents(blocks.len())
+                .len(),
+            blocks.len().div_ceil(2)
+        );
+    }
+
+    #[test]
+    fn test_ghash_with_committed_d() {
+        let mut rng = thread_rng();
+        let blocks: Vec<Gf2_128> = (0..10).map(|_| Gf2_128::rand(&mut rng)).collect();
 
         let h1: Gf2_128 = Gf2_128::rand(&mut rng);
         let h2: Gf2_128 = Gf2_128::rand(&mut rng);
@@ -144,43 +724,111 @@ mod tests {
         let mut prover = Prover::new(blocks.len(), h1);
         let mut verifier = Verifier::new(blocks.len(), h2);
 
-        let _ = ghash(&blocks, &mut prover, &mut verifier);
-
-        assert_eq!(prover.d_powers[0], Gf2_128::one());
-        assert_eq!(verifier.d_powers[0], Gf2_128::one());
-        assert_eq!(prover.d_powers[1], verifier.d_powers[1]);
-
-        assert_eq!(prover.ai[1] + verifier.bi[1], prover.r1 * verifier.r2);
-        assert_eq!(prover.h1 + verifier.h2, prover.hi[0] + verifier.hi[0]);
-        assert_eq!(prover.d_powers[1] + prover.ai[1] + verifier.bi[1], h1 + h2);
-    }
-
-    #[test]
-    fn test_pascal_tri() {
-        // This is an extension field so no naive arithmetic!
-        let pascal = pascal_tri::<Gf2_128>(4);
-
-        let expected0 = vec![Gf2_128::one()];
-        let expected1 = vec![Gf2_128::one(), Gf2_128::one()];
-        let expected2 = vec![Gf2_128::one(), Gf2_128::zero(), Gf2_128::one()];
-        let expected3 = vec![
-            Gf2_128::one(),
-            Gf2_128::one(),
-            Gf2_128::one(),
-            Gf2_128::one(),
-        ];
-        let expected4 = vec![
-            Gf2_128::one(),
-            Gf2_128::zero(),
-            Gf2_128::zero(),
-            Gf2_128::zero(),
-            Gf2_128::one(),
-        ];
-
-        assert_eq!(pascal[0], expected0);
-        assert_eq!(pascal[1], expected1);
-        assert_eq!(pascal[2], expected2);
-        assert_eq!(pascal[3], expected3);
-        assert_eq!(pascal[4], expected4);
+        let options = GhashOptions {
+            commit_d: true,
+            ..Default::default()
+        };
+        let ghash = ghash_with(&blocks, &mut prover, &mut verifier, options).unwrap();
+        assert_eq!(ghash, ghash_reference(&blocks, h1 + h2));
+    }
+
+    #[test]
+    fn test_rushing_verifier_is_caught() {
+        let mut rng = thread_rng();
+        let blocks: Vec<Gf2_128> = (0..4).map(|_| Gf2_128::rand(&mut rng)).collect();
+        let target_d = Gf2_128::rand(&mut rng);
+
+        let mut prover = Prover::new(blocks.len(), Gf2_128::rand(&mut rng));
+        let mut verifier = MaliciousVerifier::new(
+            blocks.len(),
+            Gf2_128::rand(&mut rng),
+            VerifierDeviation::RushingD(target_d),
+        );
+
+        // The verifier waits for the share of the prover and opens its share such that d is
+        // target_d, but it has committed to its share before.
+        let options = GhashOptions {
+            commit_d: true,
+            ..Default::default()
+        };
+        let result = ghash_with(&blocks, &mut prover, &mut verifier, options);
+        assert_eq!(result, Err(GhashError::InvalidOpening(Role::Receiver)));
+    }
+
+    #[test]
+    fn test_ghash_shared_blocks() {
+        let mut rng = thread_rng();
+        let blocks: Vec<Gf2_128> = (0..12).map(|_| Gf2_128::rand(&mut rng)).collect();
+
+        let h1: Gf2_128 = Gf2_128::rand(&mut rng);
+        let h2: Gf2_128 = Gf2_128::rand(&mut rng);
+        let h = h1 + h2;
+
+        // Blocks 0..3 are public, 3..6 are only known to the prover, 6..9 are only known to the
+        // verifier, and 9..12 are shared.
+        let mut prover_blocks = vec![Gf2_128::zero(); blocks.len()];
+        let mut verifier_blocks = vec![Gf2_128::zero(); blocks.len()];
+
+        for (i, &block) in blocks.iter().enumerate() {
+            match i / 3 {
+                0 | 1 => prover_blocks[i] = block,
+                2 => verifier_blocks[i] = block,
+                _ => {
+                    prover_blocks[i] = Gf2_128::rand(&mut rng);
+                    verifier_blocks[i] = block + -prover_blocks[i];
+                }
+            }
+        }
+
+        let mut prover = Prover::new(blocks.len(), h1);
+        let mut verifier = Verifier::new(blocks.len(), h2);
+
+        let ghash =
+            ghash_shared_blocks(&prover_blocks, &verifier_blocks, &mut prover, &mut verifier);
+
+        assert_eq!(ghash, ghash_reference(&blocks, h));
+    }
+
+    #[test]
+    fn test_ghash_shared_blocks_fewer_blocks() {
+        let mut rng = thread_rng();
+        let blocks: Vec<Gf2_128> = (0..5).map(|_| Gf2_128::rand(&mut rng)).collect();
+
+        let h1: Gf2_128 = Gf2_128::rand(&mut rng);
+        let h2: Gf2_128 = Gf2_128::rand(&mut rng);
+
+        let verifier_blocks = vec![Gf2_128::zero(); blocks.len()];
+
+        let mut prover = Prover::new(8, h1);
+        let mut verifier = Verifier::new(8, h2);
+
+        let ghash = ghash_shared_blocks(&blocks, &verifier_blocks, &mut prover, &mut verifier);
+
+        assert_eq!(ghash, ghash_reference(&blocks, h1 + h2));
+    }
+
+    #[test]
+    fn test_ghash_share() {
+        let mut rng = thread_rng();
+
+        let blocks: Vec<Gf2_128> = (0..1000).map(|_| Gf2_128::rand(&mut rng)).collect();
+        let xi: Vec<Gf2_128> = (0..1024).map(|_| Gf2_128::rand(&mut rng)).collect();
+
+        let mut expected = Gf2_128::zero();
+        for (i, block) in blocks.iter().enumerate() {
+            expected = expected + *block * xi[i];
+        }
+
+        assert_eq!(ghash_share(&blocks, &xi), expected);
+    }
+
+    fn ghash_reference(blocks: &[Gf2_128], h: Gf2_128) -> Gf2_128 {
+        let mut hi = vec![h];
+        compute_product_repeated(&mut hi, h, blocks.len());
+
+        blocks
+            .iter()
+            .zip(hi.iter())
+            .fold(Gf2_128::zero(), |acc, (&b, &h)| acc + (b * h))
     }
 }
@@ -1,106 +1,213 @@
-use super::pascal_tri;
-use crate::func::ole::Ole;
-use crate::func::Role;
-use delegate::delegate;
-use mpz_share_conversion_core::{
-    fields::{compute_product_repeated, gf2_128::Gf2_128, UniformRand},
-    Field,
+use super::GhashHasher;
+use crate::func::{
+    ole::Ole,
+    power_ole::{PowerOle, PowerOleError},
+    Role,
 };
-use rand::thread_rng;
-
-#[derive(Debug)]
-pub struct Prover {
-    pub(crate) block_num: usize,
-    pub(crate) h1: Gf2_128,
-    pub(crate) r1: Gf2_128,
-    pub(crate) ai: Vec<Gf2_128>,
-    pub(crate) d_powers: Vec<Gf2_128>,
-    pub(crate) hi: Vec<Gf2_128>,
-}
+use crate::polyeval;
+use delegate::delegate;
+use mpz_share_conversion_core::{fields::gf2_128::Gf2_128, Field};
+
+pub type Prover = polyeval::Prover<Gf2_128>;
 
 impl Prover {
+    /// Computes the shares of the powers of `H`.
+    ///
+    /// This is [`polyeval::Prover::handshake_a_set_xi`] under the name of the GHASH protocol.
+    pub fn handshake_a_set_hi(&mut self) {
+        self.handshake_a_set_xi();
+    }
+
+    pub fn handshake_output_ghash(&self, blocks: &[Gf2_128]) -> Gf2_128 {
+        self.ready().handshake_output_ghash(blocks)
+    }
+
+    /// Returns a hasher, which computes the same share as [`Self::handshake_output_ghash`]
+    /// incrementally.
+    pub fn hasher(&self) -> GhashHasher<'_> {
+        self.ready().hasher()
+    }
+
+    /// Returns a hasher for a GCM record with `block_num` blocks, which takes the blocks in GCM
+    /// order.
+    pub fn gcm_hasher(&self, block_num: usize) -> GhashHasher<'_> {
+        self.ready().gcm_hasher(block_num)
+    }
+
+    /// See [`super::typestate::Prover::handshake_shared_ole_input`].
+    pub fn handshake_shared_ole_input(&self, block_shares: &[Gf2_128], ole: &mut Ole<Gf2_128>) {
+        self.ready().handshake_shared_ole_input(block_shares, ole)
+    }
+
+    /// See [`super::typestate::Prover::handshake_output_ghash_shared`].
+    pub fn handshake_output_ghash_shared(
+        &self,
+        block_shares: &[Gf2_128],
+        ole: &mut Ole<Gf2_128>,
+    ) -> Gf2_128 {
+        self.ready()
+            .handshake_output_ghash_shared(block_shares, ole)
+    }
+}
+
+pub struct MaliciousProver {
+    pub inner: Prover,
+    pub ole_input: Vec<Gf2_128>,
+}
+
+impl MaliciousProver {
+    /// Create a new malicious prover, which wraps an inner prover, but sets r1 to 0.
     pub fn new(block_num: usize, h1: Gf2_128) -> Self {
-        let mut rng = thread_rng();
-        let r1 = Gf2_128::rand(&mut rng);
+        Self::with_r1(block_num, h1, Gf2_128::zero())
+    }
+
+    /// Create a new malicious prover, which uses the powers of a chosen and thus known r1.
+    pub fn with_r1(block_num: usize, h1: Gf2_128, r1: Gf2_128) -> Self {
+        let mut inner = Prover::new(block_num, h1);
+        inner.set_r1(r1);
 
         Self {
-            block_num,
-            h1,
-            r1,
-            ai: vec![],
-            d_powers: vec![],
-            hi: vec![],
+            ole_input: inner.ole_input(),
+            inner,
         }
     }
 
-    pub fn preprocess_ole_input(&self, ole: &mut Ole<Gf2_128>) {
-        let mut r1_powers = vec![Gf2_128::one()];
+    /// Create a new malicious prover, which uses the powers of a random r1, but replaces the OLE
+    /// input for the given exponents with zero.
+    pub fn with_zeroed_powers(block_num: usize, h1: Gf2_128, zeroed: &[usize]) -> Self {
+        let inner = Prover::new(block_num, h1);
+        let ole_input = inner
+            .strategy()
+            .ole_exponents(block_num)
+            .into_iter()
+            .zip(inner.ole_input())
+            .map(|(i, el)| {
+                if zeroed.contains(&i) {
+                    Gf2_128::zero()
+                } else {
+                    el
+                }
+            })
+            .collect();
+
+        Self { inner, ole_input }
+    }
+
+    /// Create a new malicious prover, which uses an arbitrary OLE input.
+    pub fn with_ole_input(block_num: usize, h1: Gf2_128, ole_input: Vec<Gf2_128>) -> Self {
+        let inner = Prover::new(block_num, h1);
+        assert_eq!(
+            ole_input.len(),
+            inner.strategy().ole_exponents(block_num).len()
+        );
 
-        compute_product_repeated(&mut r1_powers, self.r1, self.block_num);
-        ole.input(Role::Sender, r1_powers)
+        Self { inner, ole_input }
     }
 
-    pub fn preprocess_ole_output(&mut self, ole: &mut Ole<Gf2_128>) {
-        self.ai = ole.output(Role::Sender);
+    pub fn preprocess_ole_input(&self, ole: &mut Ole<Gf2_128>) {
+        ole.input(Role::Sender, self.ole_input.clone())
     }
 
-    pub fn handshake_a_open_d(&self) -> Gf2_128 {
-        self.h1 + -self.ai[1]
+    pub fn preprocess_power_ole_input(&self, ole: &mut PowerOle<Gf2_128>) {
+        ole.input(Role::Sender, self.ole_input.clone())
     }
 
-    pub fn handshake_a_set_di(&mut self, d: Gf2_128) {
-        self.d_powers = vec![Gf2_128::one(), d];
-        compute_product_repeated(&mut self.d_powers, d, self.block_num);
+    /// Returns `h` if the prover can compute it from its view.
+    ///
+    /// Since `d = h - x1 * r2`, where `x1` is the OLE input for `r^1`, this is only possible if
+    /// `x1` is zero.
+    pub fn derive_h(&self) -> Option<Gf2_128> {
+        let x = self.effective_r_powers();
+        let d = self.inner.d_powers()[1];
+
+        (x[1] == Gf2_128::zero()).then_some(d)
     }
 
-    pub fn handshake_a_set_hi(&mut self) {
-        let pascal_tri = pascal_tri::<Gf2_128>(self.block_num);
-
-        for pascal_row in pascal_tri.iter().skip(1) {
-            let h_pow_share = pascal_row
-                .iter()
-                .enumerate()
-                .fold(Gf2_128::new(0), |acc, (i, &el)| {
-                    acc + el * self.d_powers[pascal_row.len() - 1 - i] * self.ai[i]
-                });
-            self.hi.push(h_pow_share);
-        }
+    /// Returns, for every power `h^n`, the value the shares of both parties add up to, if the prover
+    /// can compute it from its view.
+    ///
+    /// The shares add up to the sum of `d^(n - k) * x_k * r2^k` over all submasks `k` of `n`, where
+    /// `x_k` is the effective OLE input for `r^k`. Without knowing `r2` this is only possible if
+    /// `x_k` is zero for every nonzero submask `k`.
+    pub fn derive_h_powers(&self) -> Vec<Option<Gf2_128>> {
+        let x = self.effective_r_powers();
+        let d_powers = self.inner.d_powers();
+
+        (1..=self.inner.degree())
+            .map(|n| {
+                let mut k = n;
+                while k > 0 {
+                    if x[k] != Gf2_128::zero() {
+                        return None;
+                    }
+                    k = (k - 1) & n;
+                }
+                Some(d_powers[n] * x[0])
+            })
+            .collect()
     }
 
-    pub fn handshake_output_ghash(&self, blocks: &[Gf2_128]) -> Gf2_128 {
-        let mut res = Gf2_128::zero();
+    /// Returns `h` if the prover can compute it from its view and the GHASH output of `blocks`.
+    ///
+    /// The output is a polynomial `c_0 + c_1 * r2 + c_2 * r2^2 + ...` in the unknown `r2`. If it is
+    /// linear, the prover solves for `r2` and computes `h = d + x1 * r2`.
+    ///
+    /// Otherwise `r2` is still one of the roots of this known polynomial, so the output leaks at
+    /// most `blocks.len()` candidates for `h`. Finding them needs root-finding in GF(2^128), which
+    /// is not implemented here, so `None` does not mean that `h` is hidden.
+    pub fn derive_h_from_output(&self, blocks: &[Gf2_128], ghash: Gf2_128) -> Option<Gf2_128> {
+        if let Some(h) = self.derive_h() {
+            return Some(h);
+        }
 
-        for (i, block) in blocks.iter().enumerate() {
-            res = res + *block * self.hi[i];
+        let x = self.effective_r_powers();
+        let d_powers = self.inner.d_powers();
+
+        let mut coefficients = vec![Gf2_128::zero(); blocks.len() + 1];
+        for (i, &block) in blocks.iter().enumerate() {
+            let n = i + 1;
+            let mut k = n;
+
+            loop {
+                coefficients[k] = coefficients[k] + block * d_powers[n - k] * x[k];
+                if k == 0 {
+                    break;
+                }
+                k = (k - 1) & n;
+            }
         }
-        res
-    }
-}
 
-pub struct MaliciousProver {
-    pub inner: Prover,
-}
+        let is_linear = coefficients.iter().skip(2).all(|&c| c == Gf2_128::zero());
+        if !is_linear || coefficients[1] == Gf2_128::zero() {
+            return None;
+        }
 
-impl MaliciousProver {
-    /// Create a new malicious prover, which wraps an inner prover, but sets r1 to 0.
-    pub fn new(block_num: usize, h1: Gf2_128) -> Self {
-        let mut prover = Self {
-            inner: Prover::new(block_num, h1),
-        };
+        let r2 = (ghash + -coefficients[0]) * coefficients[1].inverse();
+        Some(d_powers[1] + x[1] * r2)
+    }
 
-        prover.inner.r1 = Gf2_128::zero();
-        prover
+    /// Expands the OLE input into the inputs `x_k` for all powers `r^k`, which are effectively
+    /// multiplied with `r2^k`.
+    fn effective_r_powers(&self) -> Vec<Gf2_128> {
+        self.inner.strategy().r_power_shares(
+            Role::Sender,
+            self.ole_input.clone(),
+            self.inner.degree(),
+        )
     }
 
     delegate! {
         to self.inner {
-            pub fn preprocess_ole_input(&self, ole: &mut Ole<Gf2_128>);
             pub fn preprocess_ole_output(&mut self, ole: &mut Ole<Gf2_128>);
+            pub fn preprocess_power_ole_output(&mut self, ole: &mut PowerOle<Gf2_128>) -> Result<(), PowerOleError>;
             pub fn handshake_a_open_d(&self) -> Gf2_128;
             pub fn handshake_a_set_di(&mut self, d: Gf2_128);
             pub fn handshake_a_set_hi(&mut self);
             pub fn handshake_output_ghash(&self, blocks: &[Gf2_128]) -> Gf2_128;
+            pub fn hasher(&self) -> GhashHasher<'_>;
+            pub fn gcm_hasher(&self, block_num: usize) -> GhashHasher<'_>;
+            pub fn handshake_shared_ole_input(&self, block_shares: &[Gf2_128], ole: &mut Ole<Gf2_128>);
+            pub fn handshake_output_ghash_shared(&self, block_shares: &[Gf2_128], ole: &mut Ole<Gf2_128>) -> Gf2_128;
         }
-
     }
 }
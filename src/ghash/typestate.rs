@@ -0,0 +1,280 @@
+//! A typestate API for the GHASH protocol, which enforces the order of the protocol steps at
+//! compile time.
+//!
+//! Every step consumes the party in its current state and returns it in the next state, so
+//! setting the powers of `H` before `d` is known, or doing it twice, does not compile:
+//!
+//! ```compile_fail
+//! use mpz_share_conversion_core::fields::gf2_128::Gf2_128;
+//! use ole_protocols::ghash::typestate::Prover;
+//!
+//! let prover = Prover::new(1, Gf2_128::new(1));
+//! let _ = prover.handshake_output_ghash(&[Gf2_128::new(2)]);
+//! ```
+//!
+//! The parties are the parties of [`crate::polyeval::typestate`] over GF(2^128), which implement
+//! the protocol. The methods of [`super::Prover`] and [`super::Verifier`] remain as a
+//! compatibility layer on top of them.
+
+use super::{ghash_share, GhashHasher};
+use crate::func::{ole::Ole, Role};
+use crate::polyeval::typestate;
+use mpz_share_conversion_core::fields::gf2_128::Gf2_128;
+
+pub use typestate::Preprocessing;
+
+/// The party has its OLE output and waits for the opened `d`.
+pub type AwaitingD = typestate::AwaitingD<Gf2_128>;
+
+/// The party holds the shares of the powers of `H` and can compute GHASH shares.
+pub type Ready = typestate::Ready<Gf2_128>;
+
+pub type Prover<S> = typestate::Prover<Gf2_128, S>;
+pub type Verifier<S> = typestate::Verifier<Gf2_128, S>;
+
+/// Runs the GHASH protocol with the typestate API
+pub fn ghash(
+    blocks: &[Gf2_128],
+    prover: Prover<Preprocessing>,
+    verifier: Verifier<Preprocessing>,
+) -> Gf2_128 {
+    let mut ole = Ole::default();
+
+    prover.preprocess_ole_input(&mut ole);
+    verifier.preprocess_ole_input(&mut ole);
+
+    let prover = prover.preprocess_ole_output(&mut ole);
+    let verifier = verifier.preprocess_ole_output(&mut ole);
+
+    let d1 = prover.handshake_a_open_d();
+    let d2 = verifier.handshake_a_open_d();
+    let d = d1 + d2;
+
+    let prover = prover.handshake_a_set_di(d);
+    let verifier = verifier.handshake_a_set_di(d);
+
+    let ghash1 = prover.handshake_output_ghash(blocks);
+    let ghash2 = verifier.handshake_output_ghash(blocks);
+
+    ghash1 + ghash2
+}
+
+impl Prover<Ready> {
+    pub fn handshake_output_ghash(&self, blocks: &[Gf2_128]) -> Gf2_128 {
+        ghash_share(blocks, &self.state.xi)
+    }
+
+    /// Returns a hasher, which computes the same share as [`Self::handshake_output_ghash`]
+    /// incrementally.
+    pub fn hasher(&self) -> GhashHasher<'_> {
+        GhashHasher::new(&self.state.xi)
+    }
+
+    /// Returns a hasher for a GCM record with `block_num` blocks, which takes the blocks in GCM
+    /// order.
+    pub fn gcm_hasher(&self, block_num: usize) -> GhashHasher<'_> {
+        GhashHasher::new_gcm(&self.state.xi, block_num)
+    }
+
+    /// Inputs this party's shares of the blocks and of the powers of `H` into the OLE, which
+    /// computes the cross terms for [`Self::handshake_output_ghash_shared`].
+    pub fn handshake_shared_ole_input(&self, block_shares: &[Gf2_128], ole: &mut Ole<Gf2_128>) {
+        let xi = &self.state.xi;
+        assert!(
+            block_shares.len() <= xi.len(),
+            "shares of the powers of H are not ready for {} blocks",
+            block_shares.len()
+        );
+
+        ole.input(
+            Role::Sender,
+            [block_shares, &xi[..block_shares.len()]].concat(),
+        );
+    }
+
+    /// Returns the GHASH share for blocks which are additively shared between the parties.
+    ///
+    /// A block known to only one party is shared as the block itself and zero.
+    pub fn handshake_output_ghash_shared(
+        &self,
+        block_shares: &[Gf2_128],
+        ole: &mut Ole<Gf2_128>,
+    ) -> Gf2_128 {
+        let cross_terms = ole.output(Role::Sender);
+
+        cross_terms
+            .into_iter()
+            .fold(ghash_share(block_shares, &self.state.xi), |acc, x| acc + x)
+    }
+}
+
+impl Verifier<Ready> {
+    pub fn handshake_output_ghash(&self, blocks: &[Gf2_128]) -> Gf2_128 {
+        ghash_share(blocks, &self.state.xi)
+    }
+
+    /// Returns a hasher, which computes the same share as [`Self::handshake_output_ghash`]
+    /// incrementally.
+    pub fn hasher(&self) -> GhashHasher<'_> {
+        GhashHasher::new(&self.state.xi)
+    }
+
+    /// Returns a hasher for a GCM record with `block_num` blocks, which takes the blocks in GCM
+    /// order.
+    pub fn gcm_hasher(&self, block_num: usize) -> GhashHasher<'_> {
+        GhashHasher::new_gcm(&self.state.xi, block_num)
+    }
+
+    /// Inputs this party's shares of the blocks and of the powers of `H` into the OLE, which
+    /// computes the cross terms for [`Self::handshake_output_ghash_shared`].
+    pub fn handshake_shared_ole_input(&self, block_shares: &[Gf2_128], ole: &mut Ole<Gf2_128>) {
+        let xi = &self.state.xi;
+        assert!(
+            block_shares.len() <= xi.len(),
+            "shares of the powers of H are not ready for {} blocks",
+            block_shares.len()
+        );
+
+        ole.input(
+            Role::Receiver,
+            [&xi[..block_shares.len()], block_shares].concat(),
+        );
+    }
+
+    /// Returns the GHASH share for blocks which are additively shared between the parties.
+    ///
+    /// A block known to only one party is shared as the block itself and zero.
+    pub fn handshake_output_ghash_shared(
+        &self,
+        block_shares: &[Gf2_128],
+        ole: &mut Ole<Gf2_128>,
+    ) -> Gf2_128 {
+        let cross_terms = ole.output(Role::Receiver);
+
+        cross_terms
+            .into_iter()
+            .fold(ghash_share(block_shares, &self.state.xi), |acc, x| acc + x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::func::power_ole::{PowerOle, PowerOleError};
+    use crate::ghash::batch::{ghash_batch, BatchProver, BatchVerifier};
+    use crate::ghash::PowerStrategy;
+    use mpz_share_conversion_core::fields::UniformRand;
+    use mpz_share_conversion_core::Field;
+    use rand::thread_rng;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_ghash_typestate() {
+        let mut rng = thread_rng();
+        let blocks: Vec<Gf2_128> = (0..10).map(|_| Gf2_128::rand(&mut rng)).collect();
+
+        let h1: Gf2_128 = Gf2_128::rand(&mut rng);
+        let h2: Gf2_128 = Gf2_128::rand(&mut rng);
+
+        let mut prover = crate::ghash::Prover::new(blocks.len(), h1);
+        let mut verifier = crate::ghash::Verifier::new(blocks.len(), h2);
+        let expected = crate::ghash::ghash(&blocks, &mut prover, &mut verifier);
+
+        let prover = Prover::new(blocks.len(), h1);
+        let verifier = Verifier::new(blocks.len(), h2);
+
+        assert_eq!(ghash(&blocks, prover, verifier), expected);
+    }
+
+    #[test]
+    fn test_ghash_typestate_free_squaring() {
+        let mut rng = thread_rng();
+        let blocks: Vec<Gf2_128> = (0..33).map(|_| Gf2_128::rand(&mut rng)).collect();
+
+        let h1: Gf2_128 = Gf2_128::rand(&mut rng);
+        let h2: Gf2_128 = Gf2_128::rand(&mut rng);
+
+        let mut prover = crate::ghash::Prover::new(blocks.len(), h1);
+        let mut verifier = crate::ghash::Verifier::new(blocks.len(), h2);
+        let expected = crate::ghash::ghash(&blocks, &mut prover, &mut verifier);
+
+        let strategy = PowerStrategy::FreeSquaring;
+        let prover = Prover::new_with_strategy(blocks.len(), h1, strategy);
+        let verifier = Verifier::new_with_strategy(blocks.len(), h2, strategy);
+
+        let mut ole = PowerOle::new(strategy.ole_exponents(blocks.len()));
+        prover.preprocess_power_ole_input(&mut ole);
+        verifier.preprocess_power_ole_input(&mut ole);
+
+        let prover = prover.preprocess_power_ole_output(&mut ole).unwrap();
+        let verifier = verifier.preprocess_power_ole_output(&mut ole).unwrap();
+
+        let d = prover.handshake_a_open_d() + verifier.handshake_a_open_d();
+        let prover = prover.handshake_a_set_di(d);
+        let verifier = verifier.handshake_a_set_di(d);
+
+        assert_eq!(prover.state.xi.len(), blocks.len());
+        assert_eq!(
+            prover.handshake_output_ghash(&blocks) + verifier.handshake_output_ghash(&blocks),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_ghash_typestate_power_ole_abort() {
+        let mut rng = thread_rng();
+        let strategy = PowerStrategy::FreeSquaring;
+
+        let mut prover = Prover::new_with_strategy(8, Gf2_128::rand(&mut rng), strategy);
+        let verifier = Verifier::new_with_strategy(8, Gf2_128::rand(&mut rng), strategy);
+        prover.r1 = Gf2_128::zero();
+
+        let mut ole = PowerOle::new(strategy.ole_exponents(8));
+        prover.preprocess_power_ole_input(&mut ole);
+        verifier.preprocess_power_ole_input(&mut ole);
+
+        assert!(matches!(
+            prover.preprocess_power_ole_output(&mut ole),
+            Err(PowerOleError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_ghash_typestate_batch() {
+        let mut rng = thread_rng();
+        let keys = [
+            (1, 5, PowerStrategy::Full),
+            (2, 12, PowerStrategy::FreeSquaring),
+        ];
+
+        let mut provers = BTreeMap::new();
+        let mut verifiers = BTreeMap::new();
+        let mut requests = BTreeMap::new();
+        let mut expected = BTreeMap::new();
+
+        for (id, block_num, strategy) in keys {
+            let blocks: Vec<Gf2_128> = (0..block_num).map(|_| Gf2_128::rand(&mut rng)).collect();
+            let h1 = Gf2_128::rand(&mut rng);
+            let h2 = Gf2_128::rand(&mut rng);
+
+            let prover = Prover::new_with_strategy(block_num, h1, strategy);
+            let verifier = Verifier::new_with_strategy(block_num, h2, strategy);
+            expected.insert(id, ghash(&blocks, prover, verifier));
+
+            // The compatibility layer wraps typestate parties, so they can join a batch.
+            let prover = Prover::new_with_strategy(block_num, h1, strategy);
+            let verifier = Verifier::new_with_strategy(block_num, h2, strategy);
+            provers.insert(id, prover.into());
+            verifiers.insert(id, verifier.into());
+            requests.insert(id, blocks);
+        }
+
+        let mut prover = BatchProver::new(provers);
+        let mut verifier = BatchVerifier::new(verifiers);
+
+        assert_eq!(
+            ghash_batch(&requests, &mut prover, &mut verifier),
+            Ok(expected)
+        );
+    }
+}
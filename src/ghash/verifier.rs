@@ -1,77 +1,161 @@
-use super::pascal_tri;
-use crate::func::ole::Ole;
-use crate::func::Role;
-use mpz_share_conversion_core::{
-    fields::{compute_product_repeated, gf2_128::Gf2_128, UniformRand},
-    Field,
+use super::GhashHasher;
+use crate::func::{
+    commit::Decommitment,
+    ole::Ole,
+    power_ole::{PowerOle, PowerOleError},
+    Role,
 };
-use rand::thread_rng;
-
-#[derive(Debug)]
-pub struct Verifier {
-    pub(crate) block_num: usize,
-    pub(crate) h2: Gf2_128,
-    pub(crate) r2: Gf2_128,
-    pub(crate) bi: Vec<Gf2_128>,
-    pub(crate) d_powers: Vec<Gf2_128>,
-    pub(crate) hi: Vec<Gf2_128>,
-}
+use crate::polyeval;
+use delegate::delegate;
+use mpz_share_conversion_core::{fields::gf2_128::Gf2_128, Field};
+
+pub type Verifier = polyeval::Verifier<Gf2_128>;
 
 impl Verifier {
-    pub fn new(block_num: usize, h2: Gf2_128) -> Self {
-        let mut rng = thread_rng();
-        let r2 = Gf2_128::rand(&mut rng);
-
-        Self {
-            block_num,
-            h2,
-            r2,
-            bi: vec![],
-            d_powers: vec![],
-            hi: vec![],
+    /// Computes the shares of the powers of `H`.
+    ///
+    /// This is [`polyeval::Verifier::handshake_a_set_xi`] under the name of the GHASH protocol.
+    pub fn handshake_a_set_hi(&mut self) {
+        self.handshake_a_set_xi();
+    }
+
+    pub fn handshake_output_ghash(&self, blocks: &[Gf2_128]) -> Gf2_128 {
+        self.ready().handshake_output_ghash(blocks)
+    }
+
+    /// Returns a hasher, which computes the same share as [`Self::handshake_output_ghash`]
+    /// incrementally.
+    pub fn hasher(&self) -> GhashHasher<'_> {
+        self.ready().hasher()
+    }
+
+    /// Returns a hasher for a GCM record with `block_num` blocks, which takes the blocks in GCM
+    /// order.
+    pub fn gcm_hasher(&self, block_num: usize) -> GhashHasher<'_> {
+        self.ready().gcm_hasher(block_num)
+    }
+
+    /// See [`super::typestate::Verifier::handshake_shared_ole_input`].
+    pub fn handshake_shared_ole_input(&self, block_shares: &[Gf2_128], ole: &mut Ole<Gf2_128>) {
+        self.ready().handshake_shared_ole_input(block_shares, ole)
+    }
+
+    /// See [`super::typestate::Verifier::handshake_output_ghash_shared`].
+    pub fn handshake_output_ghash_shared(
+        &self,
+        block_shares: &[Gf2_128],
+        ole: &mut Ole<Gf2_128>,
+    ) -> Gf2_128 {
+        self.ready()
+            .handshake_output_ghash_shared(block_shares, ole)
+    }
+}
+
+/// The deviations of a [`MaliciousVerifier`] from the protocol.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifierDeviation {
+    /// Sets r2 to zero.
+    ZeroInput,
+    /// Replaces the OLE input `r2^k` with `t`, so the OLE input is no longer a power sequence.
+    NonPowerInput { k: usize, t: Gf2_128 },
+    /// Adds `e` to the opened share of `d`.
+    WrongDShare(Gf2_128),
+    /// Adds `e[i]` to the share of `h^(i + 1)`.
+    InconsistentHi(Vec<Gf2_128>),
+    /// Waits for the opened share of `d` from the prover and opens its own share such that `d` is
+    /// the given value.
+    RushingD(Gf2_128),
+}
+
+pub struct MaliciousVerifier {
+    pub inner: Verifier,
+    pub deviation: VerifierDeviation,
+}
+
+impl MaliciousVerifier {
+    /// Create a new malicious verifier, which wraps an inner verifier, but deviates as specified.
+    pub fn new(block_num: usize, h2: Gf2_128, deviation: VerifierDeviation) -> Self {
+        let mut verifier = Self {
+            inner: Verifier::new(block_num, h2),
+            deviation,
+        };
+
+        if verifier.deviation == VerifierDeviation::ZeroInput {
+            verifier.inner.set_r2(Gf2_128::zero());
         }
+        verifier
     }
 
     pub fn preprocess_ole_input(&self, ole: &mut Ole<Gf2_128>) {
-        let mut r2_powers = vec![Gf2_128::one()];
-
-        compute_product_repeated(&mut r2_powers, self.r2, self.block_num);
-        ole.input(Role::Receiver, r2_powers)
+        ole.input(Role::Receiver, self.ole_input())
     }
 
-    pub fn preprocess_ole_output(&mut self, ole: &mut Ole<Gf2_128>) {
-        self.bi = ole.output(Role::Receiver);
+    pub fn preprocess_power_ole_input(&self, ole: &mut PowerOle<Gf2_128>) {
+        ole.input(Role::Receiver, self.ole_input())
     }
 
     pub fn handshake_a_open_d(&self) -> Gf2_128 {
-        self.h2 + -self.bi[1]
+        let d2 = self.inner.handshake_a_open_d();
+
+        if let VerifierDeviation::WrongDShare(e) = self.deviation {
+            d2 + e
+        } else {
+            d2
+        }
     }
 
-    pub fn handshake_a_set_di(&mut self, d: Gf2_128) {
-        self.d_powers = vec![Gf2_128::one(), d];
-        compute_product_repeated(&mut self.d_powers, d, self.block_num);
+    /// Opens the committed share of `d`, after seeing the opened share `d1` of the prover.
+    pub fn handshake_a_open_committed_d(
+        &self,
+        decommitment: Decommitment<Gf2_128>,
+        d1: Gf2_128,
+    ) -> Decommitment<Gf2_128> {
+        if let VerifierDeviation::RushingD(d) = self.deviation {
+            Decommitment {
+                value: d + -d1,
+                ..decommitment
+            }
+        } else {
+            decommitment
+        }
     }
 
     pub fn handshake_a_set_hi(&mut self) {
-        let pascal_tri = pascal_tri::<Gf2_128>(self.block_num);
+        self.inner.handshake_a_set_hi();
 
-        for pascal_row in pascal_tri.iter().skip(1) {
-            let h_pow_share = pascal_row
-                .iter()
-                .enumerate()
-                .fold(Gf2_128::new(0), |acc, (i, &el)| {
-                    acc + el * self.d_powers[pascal_row.len() - 1 - i] * self.bi[i]
-                });
-            self.hi.push(h_pow_share);
+        if let VerifierDeviation::InconsistentHi(ref e) = self.deviation {
+            for (xi, &ei) in self.inner.ready_mut().state.xi.iter_mut().zip(e) {
+                *xi = *xi + ei;
+            }
         }
     }
 
-    pub fn handshake_output_ghash(&self, blocks: &[Gf2_128]) -> Gf2_128 {
-        let mut res = Gf2_128::zero();
+    delegate! {
+        to self.inner {
+            pub fn preprocess_ole_output(&mut self, ole: &mut Ole<Gf2_128>);
+            pub fn preprocess_power_ole_output(&mut self, ole: &mut PowerOle<Gf2_128>) -> Result<(), PowerOleError>;
+            pub fn handshake_a_set_di(&mut self, d: Gf2_128);
+            pub fn handshake_output_ghash(&self, blocks: &[Gf2_128]) -> Gf2_128;
+            pub fn hasher(&self) -> GhashHasher<'_>;
+            pub fn gcm_hasher(&self, block_num: usize) -> GhashHasher<'_>;
+            pub fn handshake_shared_ole_input(&self, block_shares: &[Gf2_128], ole: &mut Ole<Gf2_128>);
+            pub fn handshake_output_ghash_shared(&self, block_shares: &[Gf2_128], ole: &mut Ole<Gf2_128>) -> Gf2_128;
+        }
+    }
 
-        for (i, block) in blocks.iter().enumerate() {
-            res = res + *block * self.hi[i];
+    fn ole_input(&self) -> Vec<Gf2_128> {
+        let mut ole_input = self.inner.ole_input();
+
+        if let VerifierDeviation::NonPowerInput { k, t } = self.deviation {
+            let position = self
+                .inner
+                .strategy()
+                .ole_exponents(self.inner.degree())
+                .iter()
+                .position(|&i| i == k)
+                .expect("k should be an OLE exponent");
+            ole_input[position] = t;
         }
-        res
+        ole_input
     }
 }
@@ -2,6 +2,16 @@
 #![feature(iter_map_windows)]
 
 pub mod e2f;
+pub mod f1305;
 mod f2;
+pub mod f25519;
+pub mod fp384;
+pub mod fsecp256k1;
 pub mod func;
 pub mod ghash;
+pub mod poly1305;
+pub mod polyeval;
+pub mod polyval;
+mod prime_field;
+#[cfg(test)]
+mod test_utils;
@@ -0,0 +1,214 @@
+//! This module implements a two-party Poly1305 (RFC 8439) MAC computation, which evaluates the
+//! Poly1305 polynomial at the shared `r` with [`crate::polyeval`], like the GHASH protocol.
+//!
+//! Both parties hold XOR shares of the one-time key `(r, s)`, e.g. as output of a boolean circuit
+//! for ChaCha20. The clamp of `r` is a bitwise AND, so it is applied locally to the XOR shares,
+//! which are then converted into additive shares in GF(2^130 - 5) via OLE.
+//!
+//! The reduction modulo `p` and the addition of `s` modulo `2^128` are not linear, so they are
+//! computed with a boolean circuit. Only the tag is revealed.
+
+mod prover;
+mod tag;
+mod verifier;
+
+use crate::f1305::F1305;
+use crate::func::ole::Ole;
+use mpz_share_conversion_core::Field;
+pub use prover::Prover;
+pub use verifier::Verifier;
+
+/// The bits of `r` which are kept by the clamp.
+const CLAMP: u128 = 0x0ffffffc0ffffffc0ffffffc0fffffff;
+
+/// Returns the Poly1305 tag of `message`
+pub fn poly1305(message: &[u8], prover: &mut Prover, verifier: &mut Verifier) -> [u8; 16] {
+    let mut ole = Ole::default();
+
+    // Preprocessing
+    prover.preprocess_ole_input(&mut ole);
+    verifier.preprocess_ole_input(&mut ole);
+
+    prover.preprocess_ole_output(&mut ole);
+    verifier.preprocess_ole_output(&mut ole);
+
+    // Handshake
+    prover.handshake_a_ole_input(&mut ole);
+    verifier.handshake_a_ole_input(&mut ole);
+
+    prover.handshake_a_ole_output(&mut ole);
+    verifier.handshake_a_ole_output(&mut ole);
+
+    let d1 = prover.handshake_b_open_d();
+    let d2 = verifier.handshake_b_open_d();
+    let d = d1 + d2;
+
+    prover.handshake_b_set_di(d);
+    verifier.handshake_b_set_di(d);
+
+    prover.handshake_b_set_ri();
+    verifier.handshake_b_set_ri();
+
+    // Output
+    prover.handshake_c_set_tag(message);
+    verifier.handshake_c_set_tag(message);
+
+    let mut ole = Ole::default();
+    for _ in 0..tag::ROUNDS {
+        prover.handshake_c_ole_input(&mut ole);
+        verifier.handshake_c_ole_input(&mut ole);
+
+        prover.handshake_c_ole_output(&mut ole);
+        verifier.handshake_c_ole_output(&mut ole);
+    }
+
+    let tag1 = prover.handshake_output_tag();
+    let tag2 = verifier.handshake_output_tag();
+
+    std::array::from_fn(|i| tag1[i] ^ tag2[i])
+}
+
+/// Returns the number of 16-byte blocks of a message with `len` bytes
+pub fn block_num(len: usize) -> usize {
+    len.div_ceil(16)
+}
+
+/// Converts the message into field elements, by appending a one byte to every 16-byte block.
+fn message_to_blocks(message: &[u8]) -> Vec<F1305> {
+    message
+        .chunks(16)
+        .map(|chunk| {
+            let mut bytes = chunk.to_vec();
+            bytes.push(1);
+
+            F1305::from_le_bytes_mod_order(&bytes)
+        })
+        .collect()
+}
+
+/// Returns the coefficients of the polynomial `c_1 * r^q + ... + c_q * r`, where the `i`-th
+/// coefficient belongs to `r^i`.
+fn coefficients(message: &[u8]) -> Vec<F1305> {
+    let mut blocks = message_to_blocks(message);
+    blocks.push(F1305::zero());
+    blocks.reverse();
+
+    blocks
+}
+
+/// Returns the clamped `r` and `s` of the one-time key.
+fn split_key(key: [u8; 32]) -> (u128, [u8; 16]) {
+    let r = u128::from_le_bytes(key[..16].try_into().expect("key should have 32 bytes")) & CLAMP;
+    let s = key[16..].try_into().expect("key should have 32 bytes");
+
+    (r, s)
+}
+
+/// Returns the bits of `r` as field elements, least significant bit first.
+fn bits(r: u128) -> Vec<F1305> {
+    (0..128).map(|i| F1305::new((r >> i) & 1)).collect()
+}
+
+/// Converts the XOR share `bits` into an additive share, using the shares of the products of the
+/// bits of both parties, since `u XOR v = u + v - 2uv`.
+fn xor_to_additive_share(bits: &[F1305], products: &[F1305]) -> F1305 {
+    let two = F1305::new(2);
+
+    bits.iter()
+        .zip(products)
+        .enumerate()
+        .fold(F1305::zero(), |acc, (i, (&bit, &product))| {
+            acc + F1305::two_pow(i as u32) * (bit + -(two * product))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::hex;
+    use rand::{thread_rng, Rng};
+
+    #[test]
+    fn test_poly1305() {
+        // RFC 8439, Section 2.5.2
+        let key = hex("85d6be7857556d337f4452fe42d506a80103808afb0db2fd4abff6af4149f51b");
+        let message = b"Cryptographic Forum Research Group";
+        let expected = hex("a8061dc1305136c6c22b8baf0c0127a9");
+
+        assert_eq!(poly1305_shared(key, message), expected[..]);
+    }
+
+    #[test]
+    fn test_poly1305_vectors() {
+        // RFC 8439, Appendix A.3, test vectors #1, #5, #6 and #7
+        let vectors = [
+            ("00".repeat(32), "00".repeat(64), "00".repeat(16)),
+            (
+                format!("02{}", "00".repeat(31)),
+                "ff".repeat(16),
+                format!("03{}", "00".repeat(15)),
+            ),
+            (
+                format!("02{}{}", "00".repeat(15), "ff".repeat(16)),
+                format!("02{}", "00".repeat(15)),
+                format!("03{}", "00".repeat(15)),
+            ),
+            (
+                format!("01{}", "00".repeat(31)),
+                format!(
+                    "{}f0{}11{}",
+                    "ff".repeat(16),
+                    "ff".repeat(15),
+                    "00".repeat(15)
+                ),
+                format!("05{}", "00".repeat(15)),
+            ),
+        ];
+
+        for (key, message, expected) in vectors {
+            let key = hex(&key);
+            let message = hex(&message);
+            let expected = hex(&expected);
+
+            assert_eq!(poly1305_shared(key, &message), expected[..]);
+        }
+    }
+
+    #[test]
+    fn test_poly1305_empty_message() {
+        // The polynomial of an empty message is 0, so the tag is `s`
+        let key: [u8; 32] = thread_rng().gen();
+
+        assert_eq!(poly1305_shared(key, b""), key[16..]);
+    }
+
+    #[test]
+    fn test_xor_to_additive_share() {
+        let mut rng = thread_rng();
+        let key1: [u8; 32] = rng.gen();
+        let key2: [u8; 32] = rng.gen();
+        let key: [u8; 32] = std::array::from_fn(|i| key1[i] ^ key2[i]);
+
+        let message = [0_u8; 16];
+        let mut prover = Prover::new(block_num(message.len()), key1);
+        let mut verifier = Verifier::new(block_num(message.len()), key2);
+        let _ = poly1305(&message, &mut prover, &mut verifier);
+
+        let (r, _) = split_key(key);
+        assert_eq!(
+            prover.inner.ready().x1 + verifier.inner.ready().x2,
+            F1305::new(r)
+        );
+    }
+
+    fn poly1305_shared(key: impl AsRef<[u8]>, message: &[u8]) -> [u8; 16] {
+        let key: [u8; 32] = key.as_ref().try_into().unwrap();
+        let key1: [u8; 32] = thread_rng().gen();
+        let key2: [u8; 32] = std::array::from_fn(|i| key[i] ^ key1[i]);
+
+        let mut prover = Prover::new(block_num(message.len()), key1);
+        let mut verifier = Verifier::new(block_num(message.len()), key2);
+
+        poly1305(message, &mut prover, &mut verifier)
+    }
+}
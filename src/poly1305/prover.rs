@@ -0,0 +1,94 @@
+use super::tag::TagCircuit;
+use super::{bits, coefficients, split_key, xor_to_additive_share};
+use crate::f1305::F1305;
+use crate::f2::F2;
+use crate::func::{ole::Ole, Role};
+use crate::polyeval;
+use mpz_share_conversion_core::Field;
+
+#[derive(Debug)]
+pub struct Prover {
+    pub(crate) inner: polyeval::Prover<F1305>,
+    pub(crate) r1_xor: u128,
+    pub(crate) s1: [u8; 16],
+    pub(crate) tag: Option<TagCircuit>,
+}
+
+impl Prover {
+    /// Creates a new prover, which holds an XOR share of the 32-byte one-time key.
+    ///
+    /// The additive share of `r` is only known after [`Self::handshake_a_ole_output`], so the inner
+    /// prover starts with a zero share.
+    pub fn new(block_num: usize, key1: [u8; 32]) -> Self {
+        let (r1_xor, s1) = split_key(key1);
+
+        Self {
+            inner: polyeval::Prover::new(block_num, F1305::zero()),
+            r1_xor,
+            s1,
+            tag: None,
+        }
+    }
+
+    pub fn preprocess_ole_input(&self, ole: &mut Ole<F1305>) {
+        self.inner.preprocess_ole_input(ole);
+    }
+
+    pub fn preprocess_ole_output(&mut self, ole: &mut Ole<F1305>) {
+        self.inner.preprocess_ole_output(ole);
+    }
+
+    pub fn handshake_a_ole_input(&self, ole: &mut Ole<F1305>) {
+        ole.input(Role::Sender, bits(self.r1_xor));
+    }
+
+    pub fn handshake_a_ole_output(&mut self, ole: &mut Ole<F1305>) {
+        let products = ole.output(Role::Sender);
+        self.inner
+            .set_x1(xor_to_additive_share(&bits(self.r1_xor), &products));
+    }
+
+    pub fn handshake_b_open_d(&self) -> F1305 {
+        self.inner.handshake_a_open_d()
+    }
+
+    pub fn handshake_b_set_di(&mut self, d: F1305) {
+        self.inner.handshake_a_set_di(d);
+    }
+
+    pub fn handshake_b_set_ri(&mut self) {
+        self.inner.handshake_a_set_xi();
+    }
+
+    pub fn handshake_output_acc(&self, message: &[u8]) -> F1305 {
+        self.inner.handshake_output_eval(&coefficients(message))
+    }
+
+    /// Starts the computation of the tag from the shares of the polynomial and of `s`.
+    pub fn handshake_c_set_tag(&mut self, message: &[u8]) {
+        let acc = self.handshake_output_acc(message);
+        self.tag = Some(TagCircuit::new(Role::Sender, acc, self.s1));
+    }
+
+    pub fn handshake_c_ole_input(&self, ole: &mut Ole<F2>) {
+        self.tag
+            .as_ref()
+            .expect("tag circuit should be set")
+            .ole_input(ole);
+    }
+
+    pub fn handshake_c_ole_output(&mut self, ole: &mut Ole<F2>) {
+        self.tag
+            .as_mut()
+            .expect("tag circuit should be set")
+            .ole_output(ole);
+    }
+
+    /// Returns the XOR share of the tag, which can be opened.
+    pub fn handshake_output_tag(&self) -> [u8; 16] {
+        self.tag
+            .as_ref()
+            .expect("tag circuit should be set")
+            .output()
+    }
+}
@@ -0,0 +1,224 @@
+//! The final step of Poly1305, which computes `(acc mod p + s) mod 2^128` without revealing `acc`
+//! or `s`.
+//!
+//! The parties decompose their additive shares of `acc` and their XOR shares of `s` into bits and
+//! evaluate a boolean circuit on XOR-shared bits. Every AND gate is computed with an OLE over F2.
+//! With `A = acc1 + acc2` as integers and `p = 2^130 - 5`,
+//!
+//! - `B = A + 5` is computed with a ripple-carry adder, so bit 130 of `B` is `w = [A >= p]`,
+//! - `acc = A - w * p` is `A + 5 * w` modulo `2^128`,
+//! - so the tag is `s + B + (1 - w) * (2^128 - 5)` modulo `2^128`, which takes two more adders.
+//!
+//! Only the XOR shares of the tag are opened. The share of the other party is the tag plus the own
+//! share, so this reveals nothing but the tag.
+
+use crate::f1305::F1305;
+use crate::f2::F2;
+use crate::func::{ole::Ole, Role};
+use mpz_share_conversion_core::Field;
+
+/// The number of OLE rounds, one per bit of each of the three adders.
+pub(crate) const ROUNDS: usize = 131 + 128 + 128;
+
+/// The constant `2^128 - 5`, which is added to the tag if `A < p`.
+const MINUS_FIVE: u128 = u128::MAX - 4;
+
+/// One party's view of the tag circuit.
+#[derive(Debug)]
+pub(crate) struct TagCircuit {
+    role: Role,
+    s: Vec<F2>,
+    k: Vec<F2>,
+    adder: Adder,
+    stage: usize,
+}
+
+impl TagCircuit {
+    /// Creates the circuit from the additive share of `acc` and the XOR share of `s`.
+    pub(crate) fn new(role: Role, acc: F1305, s: [u8; 16]) -> Self {
+        let bytes = acc.to_le_bytes();
+        let low = u128::from_le_bytes(bytes[..16].try_into().expect("acc should have 17 bytes"));
+        let high = bytes[16] as u128;
+
+        // Only the prover adds 5, and only the verifier inputs its share as second summand.
+        let (x, y) = match role {
+            Role::Sender => {
+                let (low, carry) = low.overflowing_add(5);
+                let x = [bits(low, 128), bits(high + carry as u128, 3)].concat();
+                (x, vec![F2::zero(); 131])
+            }
+            Role::Receiver => (
+                vec![F2::zero(); 131],
+                [bits(low, 128), bits(high, 3)].concat(),
+            ),
+        };
+
+        Self {
+            role,
+            s: bits(u128::from_le_bytes(s), 128),
+            k: vec![],
+            adder: Adder::new(x, y),
+            stage: 0,
+        }
+    }
+
+    pub(crate) fn ole_input(&self, ole: &mut Ole<F2>) {
+        self.adder.ole_input(self.role, ole);
+    }
+
+    pub(crate) fn ole_output(&mut self, ole: &mut Ole<F2>) {
+        self.adder.ole_output(self.role, ole);
+
+        if !self.adder.is_done() {
+            return;
+        }
+
+        match self.stage {
+            0 => {
+                let b = std::mem::take(&mut self.adder.sum);
+
+                // The prover adds the public 1 to its share of w to get a share of 1 - w.
+                let not_w = if self.role == Role::Sender {
+                    b[130] + F2::one()
+                } else {
+                    b[130]
+                };
+                self.k = bits(MINUS_FIVE, 128)
+                    .into_iter()
+                    .map(|bit| bit * not_w)
+                    .collect();
+
+                self.adder = Adder::new(self.s.clone(), b[..128].to_vec());
+            }
+            1 => {
+                let c = std::mem::take(&mut self.adder.sum);
+                self.adder = Adder::new(c, std::mem::take(&mut self.k));
+            }
+            _ => {}
+        }
+        self.stage += 1;
+    }
+
+    /// Returns the XOR share of the tag.
+    pub(crate) fn output(&self) -> [u8; 16] {
+        assert_eq!(self.stage, 3, "the tag circuit has not finished");
+
+        let tag = self
+            .adder
+            .sum
+            .iter()
+            .enumerate()
+            .fold(0_u128, |acc, (i, &bit)| {
+                acc | ((bit == F2::one()) as u128) << i
+            });
+
+        tag.to_le_bytes()
+    }
+}
+
+/// A ripple-carry adder for XOR-shared bits, least significant bit first.
+///
+/// The carry is `c' = c + (x + c) * (y + c)`, so every bit needs one AND gate, whose cross terms
+/// are computed with an OLE.
+#[derive(Debug)]
+struct Adder {
+    x: Vec<F2>,
+    y: Vec<F2>,
+    carry: F2,
+    sum: Vec<F2>,
+}
+
+impl Adder {
+    fn new(x: Vec<F2>, y: Vec<F2>) -> Self {
+        assert_eq!(x.len(), y.len());
+
+        Self {
+            x,
+            y,
+            carry: F2::zero(),
+            sum: vec![],
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.sum.len() == self.x.len()
+    }
+
+    /// Returns the shares of `x + c` and `y + c` for the current bit.
+    fn and_inputs(&self) -> (F2, F2) {
+        let i = self.sum.len();
+        (self.x[i] + self.carry, self.y[i] + self.carry)
+    }
+
+    fn ole_input(&self, role: Role, ole: &mut Ole<F2>) {
+        let (u, v) = self.and_inputs();
+
+        // The sender inputs (u1, v1) and the receiver (v2, u2), which yields u1 * v2 and v1 * u2.
+        let input = match role {
+            Role::Sender => vec![u, v],
+            Role::Receiver => vec![v, u],
+        };
+        ole.input(role, input);
+    }
+
+    fn ole_output(&mut self, role: Role, ole: &mut Ole<F2>) {
+        let cross_terms = ole.output(role);
+        let (u, v) = self.and_inputs();
+        let i = self.sum.len();
+
+        self.sum.push(self.x[i] + self.y[i] + self.carry);
+        self.carry = self.carry + u * v + cross_terms[0] + cross_terms[1];
+    }
+}
+
+/// Returns the lowest `n` bits of `value`, least significant bit first.
+fn bits(value: u128, n: usize) -> Vec<F2> {
+    (0..n).map(|i| F2::new((value >> i) & 1 == 1)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mpz_share_conversion_core::fields::UniformRand;
+    use rand::{thread_rng, Rng};
+
+    #[test]
+    fn test_tag_circuit() {
+        let mut rng = thread_rng();
+        let p_minus_one = -F1305::one();
+
+        // The second case wraps around p, the third one reduces to exactly 0.
+        let cases = [
+            (F1305::rand(&mut rng), F1305::rand(&mut rng)),
+            (p_minus_one, p_minus_one),
+            (p_minus_one, F1305::one()),
+        ];
+
+        for (acc1, acc2) in cases {
+            let s1: [u8; 16] = rng.gen();
+            let s2: [u8; 16] = rng.gen();
+
+            let mut prover = TagCircuit::new(Role::Sender, acc1, s1);
+            let mut verifier = TagCircuit::new(Role::Receiver, acc2, s2);
+
+            let mut ole = Ole::default();
+            for _ in 0..ROUNDS {
+                prover.ole_input(&mut ole);
+                verifier.ole_input(&mut ole);
+                prover.ole_output(&mut ole);
+                verifier.ole_output(&mut ole);
+            }
+
+            let tag1 = prover.output();
+            let tag2 = verifier.output();
+            let tag: [u8; 16] = std::array::from_fn(|i| tag1[i] ^ tag2[i]);
+
+            let acc = acc1 + acc2;
+            let s = u128::from_le_bytes(s1) ^ u128::from_le_bytes(s2);
+            let expected =
+                u128::from_le_bytes(acc.to_le_bytes()[..16].try_into().unwrap()).wrapping_add(s);
+
+            assert_eq!(tag, expected.to_le_bytes());
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use super::tag::TagCircuit;
+use super::{bits, coefficients, split_key, xor_to_additive_share};
+use crate::f1305::F1305;
+use crate::f2::F2;
+use crate::func::{ole::Ole, Role};
+use crate::polyeval;
+use mpz_share_conversion_core::Field;
+
+#[derive(Debug)]
+pub struct Verifier {
+    pub(crate) inner: polyeval::Verifier<F1305>,
+    pub(crate) r2_xor: u128,
+    pub(crate) s2: [u8; 16],
+    pub(crate) tag: Option<TagCircuit>,
+}
+
+impl Verifier {
+    /// Creates a new verifier, which holds an XOR share of the 32-byte one-time key.
+    ///
+    /// The additive share of `r` is only known after [`Self::handshake_a_ole_output`], so the inner
+    /// verifier starts with a zero share.
+    pub fn new(block_num: usize, key2: [u8; 32]) -> Self {
+        let (r2_xor, s2) = split_key(key2);
+
+        Self {
+            inner: polyeval::Verifier::new(block_num, F1305::zero()),
+            r2_xor,
+            s2,
+            tag: None,
+        }
+    }
+
+    pub fn preprocess_ole_input(&self, ole: &mut Ole<F1305>) {
+        self.inner.preprocess_ole_input(ole);
+    }
+
+    pub fn preprocess_ole_output(&mut self, ole: &mut Ole<F1305>) {
+        self.inner.preprocess_ole_output(ole);
+    }
+
+    pub fn handshake_a_ole_input(&self, ole: &mut Ole<F1305>) {
+        ole.input(Role::Receiver, bits(self.r2_xor));
+    }
+
+    pub fn handshake_a_ole_output(&mut self, ole: &mut Ole<F1305>) {
+        let products = ole.output(Role::Receiver);
+        self.inner
+            .set_x2(xor_to_additive_share(&bits(self.r2_xor), &products));
+    }
+
+    pub fn handshake_b_open_d(&self) -> F1305 {
+        self.inner.handshake_a_open_d()
+    }
+
+    pub fn handshake_b_set_di(&mut self, d: F1305) {
+        self.inner.handshake_a_set_di(d);
+    }
+
+    pub fn handshake_b_set_ri(&mut self) {
+        self.inner.handshake_a_set_xi();
+    }
+
+    pub fn handshake_output_acc(&self, message: &[u8]) -> F1305 {
+        self.inner.handshake_output_eval(&coefficients(message))
+    }
+
+    /// Starts the computation of the tag from the shares of the polynomial and of `s`.
+    pub fn handshake_c_set_tag(&mut self, message: &[u8]) {
+        let acc = self.handshake_output_acc(message);
+        self.tag = Some(TagCircuit::new(Role::Receiver, acc, self.s2));
+    }
+
+    pub fn handshake_c_ole_input(&self, ole: &mut Ole<F2>) {
+        self.tag
+            .as_ref()
+            .expect("tag circuit should be set")
+            .ole_input(ole);
+    }
+
+    pub fn handshake_c_ole_output(&mut self, ole: &mut Ole<F2>) {
+        self.tag
+            .as_mut()
+            .expect("tag circuit should be set")
+            .ole_output(ole);
+    }
+
+    /// Returns the XOR share of the tag, which can be opened.
+    pub fn handshake_output_tag(&self) -> [u8; 16] {
+        self.tag
+            .as_ref()
+            .expect("tag circuit should be set")
+            .output()
+    }
+}
@@ -0,0 +1,397 @@
+//! This module evaluates a polynomial with public coefficients at an additively shared point.
+//!
+//! It generalizes the power sharing of the GHASH protocol (page 36) from
+//! <https://eprint.iacr.org/2023/964> to arbitrary fields. The parties open `d = x - r`, where
+//! the shares of the powers of the mask `r` come from an OLE, and expand the shares of the powers
+//! of `x = d + r` with the binomial theorem.
+
+mod prover;
+pub mod typestate;
+mod verifier;
+
+use crate::f1305::F1305;
+use crate::func::{ole::Ole, Role};
+use mpz_share_conversion_core::{
+    fields::{gf2_128::Gf2_128, p256::P256},
+    Field,
+};
+pub use prover::Prover;
+use std::ops::Range;
+pub use verifier::Verifier;
+
+/// A field, which exposes whether it has characteristic 2.
+///
+/// In characteristic 2 binomial coefficients can be computed with Lucas' theorem and squaring is
+/// linear, which makes the power expansion cheaper.
+pub trait Characteristic: Field {
+    const IS_TWO: bool;
+}
+
+impl Characteristic for Gf2_128 {
+    const IS_TWO: bool = true;
+}
+
+impl Characteristic for P256 {
+    const IS_TWO: bool = false;
+}
+
+impl Characteristic for F1305 {
+    const IS_TWO: bool = false;
+}
+
+/// Returns the evaluation of the polynomial with the given coefficients at the shared point,
+/// where `coefficients[i]` belongs to `x^i`.
+pub fn polyeval<T: Characteristic>(
+    coefficients: &[T],
+    prover: &mut Prover<T>,
+    verifier: &mut Verifier<T>,
+) -> T {
+    let mut ole = Ole::default();
+
+    prover.preprocess_ole_input(&mut ole);
+    verifier.preprocess_ole_input(&mut ole);
+
+    prover.preprocess_ole_output(&mut ole);
+    verifier.preprocess_ole_output(&mut ole);
+
+    let d1 = prover.handshake_a_open_d();
+    let d2 = verifier.handshake_a_open_d();
+    let d = d1 + d2;
+
+    prover.handshake_a_set_di(d);
+    verifier.handshake_a_set_di(d);
+
+    prover.handshake_a_set_xi();
+    verifier.handshake_a_set_xi();
+
+    let eval1 = prover.handshake_output_eval(coefficients);
+    let eval2 = verifier.handshake_output_eval(coefficients);
+
+    eval1 + eval2
+}
+
+/// The strategy used to compute the shares of the powers of `x`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum PowerStrategy {
+    /// Shares all powers `r^0, ..., r^n` via OLE and expands every power of `x` from them.
+    #[default]
+    Full,
+    /// Shares only the odd powers of `r` via OLE and only expands the odd powers of `x`.
+    ///
+    /// Because squaring is linear in characteristic 2, all even powers are obtained by locally
+    /// squaring shares, which roughly halves the OLE consumption and the work for the powers of
+    /// `x`. Only available for fields of characteristic 2.
+    FreeSquaring,
+}
+
+impl PowerStrategy {
+    /// Returns the exponents of `r` whose shares are computed via OLE.
+    pub fn ole_exponents(&self, degree: usize) -> Vec<usize> {
+        match self {
+            PowerStrategy::Full => (0..=degree).collect(),
+            PowerStrategy::FreeSquaring => (1..=degree).step_by(2).collect(),
+        }
+    }
+
+    /// Expands the OLE output into shares of all powers `r^0, ..., r^n`.
+    pub(crate) fn r_power_shares<T: Characteristic>(
+        &self,
+        role: Role,
+        ole_output: Vec<T>,
+        degree: usize,
+    ) -> Vec<T> {
+        match self {
+            PowerStrategy::Full => ole_output,
+            PowerStrategy::FreeSquaring => {
+                let one_share = if role == Role::Sender {
+                    T::one()
+                } else {
+                    T::zero()
+                };
+
+                let mut odd_shares = ole_output.into_iter();
+                let mut r_power_shares = vec![one_share];
+
+                for i in 1..=degree {
+                    let r_pow_share = if i % 2 == 1 {
+                        odd_shares
+                            .next()
+                            .expect("OLE output should contain all odd powers")
+                    } else {
+                        r_power_shares[i / 2] * r_power_shares[i / 2]
+                    };
+                    r_power_shares.push(r_pow_share);
+                }
+                r_power_shares
+            }
+        }
+    }
+
+    /// Computes the shares of `x^1, ..., x^n` for this strategy.
+    pub(crate) fn x_power_shares<T: Characteristic>(
+        &self,
+        d_powers: &[T],
+        r_power_shares: &[T],
+        degree: usize,
+    ) -> Vec<T> {
+        match self {
+            PowerStrategy::Full if T::IS_TWO => {
+                power_shares_lucas(d_powers, r_power_shares, degree)
+            }
+            PowerStrategy::Full => power_shares_pascal(d_powers, r_power_shares, degree),
+            PowerStrategy::FreeSquaring => {
+                power_shares_free_squaring(d_powers, r_power_shares, degree)
+            }
+        }
+    }
+}
+
+/// Computes additive shares of `x^1, ..., x^degree` from the opened powers of `d` and the
+/// shares of the powers of `r`, using the binomial expansion `x^n = (d + r)^n`.
+///
+/// This materializes the whole Pascal triangle, so it needs O(n^2) memory and multiplications.
+pub fn power_shares_pascal<T: Field>(
+    d_powers: &[T],
+    r_power_shares: &[T],
+    degree: usize,
+) -> Vec<T> {
+    let pascal_tri = pascal_tri::<T>(degree);
+
+    pascal_tri
+        .iter()
+        .skip(1)
+        .map(|pascal_row| {
+            pascal_row
+                .iter()
+                .enumerate()
+                .fold(T::zero(), |acc, (i, &el)| {
+                    acc + el * d_powers[pascal_row.len() - 1 - i] * r_power_shares[i]
+                })
+        })
+        .collect()
+}
+
+/// Computes the same shares as [`power_shares_pascal`], but only for fields of characteristic 2.
+///
+/// By Lucas' theorem `C(n, k) mod 2 = 1` if and only if `k & !n == 0`, so we only visit the
+/// submasks `k` of `n` and skip all zero terms. This needs no extra memory and
+/// O(n^log2(3)) multiplications in total.
+///
+/// # Panics
+///
+/// Panics if `T` does not have characteristic 2.
+pub fn power_shares_lucas<T: Characteristic>(
+    d_powers: &[T],
+    r_power_shares: &[T],
+    degree: usize,
+) -> Vec<T> {
+    assert!(
+        T::IS_TWO,
+        "Lucas' theorem needs a field of characteristic 2"
+    );
+
+    map_range(1..degree + 1, |n| {
+        power_share_lucas(d_powers, r_power_shares, n)
+    })
+}
+
+/// Computes the same shares as [`power_shares_lucas`], but only expands the odd powers.
+///
+/// In characteristic 2 squaring is linear, so the share of `x^2n` is the square of the share of
+/// `x^n`. For the odd powers only shares of the odd powers of `r` need to come from the OLE, see
+/// [`PowerStrategy::FreeSquaring`].
+///
+/// # Panics
+///
+/// Panics if `T` does not have characteristic 2.
+pub fn power_shares_free_squaring<T: Characteristic>(
+    d_powers: &[T],
+    r_power_shares: &[T],
+    degree: usize,
+) -> Vec<T> {
+    assert!(T::IS_TWO, "free squaring needs a field of characteristic 2");
+
+    let odd_shares = map_range(0..degree.div_ceil(2), |j| {
+        power_share_lucas(d_powers, r_power_shares, 2 * j + 1)
+    });
+    let mut xi: Vec<T> = Vec::with_capacity(degree);
+
+    for n in 1..=degree {
+        let x_pow_share = if n % 2 == 1 {
+            odd_shares[n / 2]
+        } else {
+            xi[n / 2 - 1] * xi[n / 2 - 1]
+        };
+        xi.push(x_pow_share);
+    }
+    xi
+}
+
+/// Collects `f(i)` for every `i` in `range`, in parallel if the `rayon` feature is enabled.
+///
+/// The items are independent of each other, so both paths return the same vector.
+pub(crate) fn map_range<T, F>(range: Range<usize>, f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(usize) -> T + Send + Sync,
+{
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        range.into_par_iter().map(f).collect()
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+        range.map(f).collect()
+    }
+}
+
+fn power_share_lucas<T: Characteristic>(d_powers: &[T], r_power_shares: &[T], n: usize) -> T {
+    let mut x_pow_share = T::zero();
+    let mut k = n;
+
+    loop {
+        x_pow_share = x_pow_share + d_powers[n - k] * r_power_shares[k];
+        if k == 0 {
+            break;
+        }
+        k = (k - 1) & n;
+    }
+    x_pow_share
+}
+
+fn pascal_tri<T: Field>(n: usize) -> Vec<Vec<T>> {
+    let mut pascal = vec![vec![T::one()]];
+
+    for _ in 0..n {
+        let last_row = pascal.last().unwrap();
+        let mut new_row = vec![T::one()];
+
+        last_row
+            .iter()
+            .map_windows(|[&a, &b]| a + b)
+            .for_each(|el| {
+                new_row.push(el);
+            });
+        new_row.push(T::one());
+        pascal.push(new_row);
+    }
+    pascal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mpz_share_conversion_core::fields::{compute_product_repeated, UniformRand};
+    use rand::thread_rng;
+
+    #[test]
+    fn test_polyeval() {
+        test_polyeval_for::<Gf2_128>(PowerStrategy::Full);
+        test_polyeval_for::<Gf2_128>(PowerStrategy::FreeSquaring);
+        test_polyeval_for::<P256>(PowerStrategy::Full);
+        test_polyeval_for::<F1305>(PowerStrategy::Full);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_free_squaring_needs_characteristic_two() {
+        let mut rng = thread_rng();
+        let _ = Prover::new_with_strategy(10, P256::rand(&mut rng), PowerStrategy::FreeSquaring);
+    }
+
+    #[test]
+    fn test_power_shares_lucas() {
+        let mut rng = thread_rng();
+        let degree = 100;
+
+        let d_powers: Vec<Gf2_128> = (0..=degree).map(|_| Gf2_128::rand(&mut rng)).collect();
+        let r_power_shares: Vec<Gf2_128> = (0..=degree).map(|_| Gf2_128::rand(&mut rng)).collect();
+
+        let expected = power_shares_pascal(&d_powers, &r_power_shares, degree);
+        let shares = power_shares_lucas(&d_powers, &r_power_shares, degree);
+
+        assert_eq!(shares, expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "Lucas' theorem needs a field of characteristic 2")]
+    fn test_power_shares_lucas_needs_characteristic_two() {
+        let mut rng = thread_rng();
+        let shares: Vec<P256> = (0..3).map(|_| P256::rand(&mut rng)).collect();
+
+        let _ = power_shares_lucas(&shares, &shares, 2);
+    }
+
+    #[test]
+    fn test_power_shares_free_squaring() {
+        let mut rng = thread_rng();
+        let degree = 100;
+
+        // Free squaring needs consistent powers of r, so one party holds all of them here.
+        let d = Gf2_128::rand(&mut rng);
+        let r = Gf2_128::rand(&mut rng);
+
+        let mut d_powers = vec![Gf2_128::one(), d];
+        compute_product_repeated(&mut d_powers, d, degree - 1);
+        let mut r_powers = vec![Gf2_128::one(), r];
+        compute_product_repeated(&mut r_powers, r, degree - 1);
+
+        let expected = power_shares_lucas(&d_powers, &r_powers, degree);
+        let shares = power_shares_free_squaring(&d_powers, &r_powers, degree);
+
+        assert_eq!(shares, expected);
+        assert_eq!(shares[degree - 1], expected[0] * shares[degree - 2]);
+    }
+
+    #[test]
+    fn test_pascal_tri() {
+        // This is an extension field so no naive arithmetic!
+        let pascal = pascal_tri::<Gf2_128>(4);
+
+        let expected0 = vec![Gf2_128::one()];
+        let expected1 = vec![Gf2_128::one(), Gf2_128::one()];
+        let expected2 = vec![Gf2_128::one(), Gf2_128::zero(), Gf2_128::one()];
+        let expected3 = vec![
+            Gf2_128::one(),
+            Gf2_128::one(),
+            Gf2_128::one(),
+            Gf2_128::one(),
+        ];
+        let expected4 = vec![
+            Gf2_128::one(),
+            Gf2_128::zero(),
+            Gf2_128::zero(),
+            Gf2_128::zero(),
+            Gf2_128::one(),
+        ];
+
+        assert_eq!(pascal[0], expected0);
+        assert_eq!(pascal[1], expected1);
+        assert_eq!(pascal[2], expected2);
+        assert_eq!(pascal[3], expected3);
+        assert_eq!(pascal[4], expected4);
+    }
+
+    fn test_polyeval_for<T: Characteristic>(strategy: PowerStrategy) {
+        let mut rng = thread_rng();
+        let coefficients: Vec<T> = (0..12).map(|_| T::rand(&mut rng)).collect();
+        let degree = coefficients.len() - 1;
+
+        let x1 = T::rand(&mut rng);
+        let x2 = T::rand(&mut rng);
+        let x = x1 + x2;
+
+        let mut prover = Prover::new_with_strategy(degree, x1, strategy);
+        let mut verifier = Verifier::new_with_strategy(degree, x2, strategy);
+
+        let eval = polyeval(&coefficients, &mut prover, &mut verifier);
+
+        let eval_expected = coefficients
+            .iter()
+            .rev()
+            .fold(T::zero(), |acc, &c| acc * x + c);
+
+        assert_eq!(eval, eval_expected);
+    }
+}
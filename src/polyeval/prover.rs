@@ -0,0 +1,170 @@
+use super::typestate::{self, AwaitingD, Preprocessing, Ready};
+use super::{Characteristic, PowerStrategy};
+use crate::func::{
+    ole::Ole,
+    power_ole::{PowerOle, PowerOleError},
+};
+
+/// A compatibility layer over [`typestate::Prover`], which keeps the current state behind a
+/// mutable reference.
+///
+/// Calling a method in the wrong state panics instead of failing to compile.
+#[derive(Debug)]
+pub struct Prover<T: Characteristic> {
+    state: Option<State<T>>,
+}
+
+#[derive(Debug)]
+enum State<T: Characteristic> {
+    Preprocessing(typestate::Prover<T, Preprocessing>),
+    AwaitingD(typestate::Prover<T, AwaitingD<T>>),
+    Ready(typestate::Prover<T, Ready<T>>),
+}
+
+impl<T: Characteristic> Prover<T> {
+    pub fn new(degree: usize, x1: T) -> Self {
+        typestate::Prover::new(degree, x1).into()
+    }
+
+    pub fn new_with_strategy(degree: usize, x1: T, strategy: PowerStrategy) -> Self {
+        typestate::Prover::new_with_strategy(degree, x1, strategy).into()
+    }
+
+    pub fn preprocess_ole_input(&self, ole: &mut Ole<T>) {
+        self.preprocessing().preprocess_ole_input(ole)
+    }
+
+    pub fn preprocess_ole_output(&mut self, ole: &mut Ole<T>) {
+        let prover = self.take_preprocessing().preprocess_ole_output(ole);
+        self.state = Some(State::AwaitingD(prover));
+    }
+
+    pub fn preprocess_power_ole_input(&self, ole: &mut PowerOle<T>) {
+        self.preprocessing().preprocess_power_ole_input(ole)
+    }
+
+    /// Runs the output step of the power OLE.
+    ///
+    /// If the power OLE aborts, the prover is left without state, like the typestate prover which
+    /// is consumed by the failed step.
+    pub fn preprocess_power_ole_output(
+        &mut self,
+        ole: &mut PowerOle<T>,
+    ) -> Result<(), PowerOleError> {
+        let prover = self.take_preprocessing().preprocess_power_ole_output(ole)?;
+        self.state = Some(State::AwaitingD(prover));
+        Ok(())
+    }
+
+    pub fn handshake_a_open_d(&self) -> T {
+        match &self.state {
+            Some(State::AwaitingD(prover)) => prover.handshake_a_open_d(),
+            _ => panic!("the OLE has not been run"),
+        }
+    }
+
+    /// Sets the opened `d` and computes the shares of the powers of `x`.
+    pub fn handshake_a_set_di(&mut self, d: T) {
+        let prover = match self.state.take() {
+            Some(State::AwaitingD(prover)) => prover.handshake_a_set_di(d),
+            _ => panic!("the OLE has not been run"),
+        };
+        self.state = Some(State::Ready(prover));
+    }
+
+    /// Checks that the shares of the powers of `x` are available.
+    ///
+    /// They are already computed by [`Self::handshake_a_set_di`], so this step only remains for
+    /// callers of the earlier API.
+    pub fn handshake_a_set_xi(&mut self) {
+        self.ready();
+    }
+
+    /// Returns the share of the polynomial with the given coefficients, where `coefficients[i]`
+    /// belongs to `x^i`.
+    pub fn handshake_output_eval(&self, coefficients: &[T]) -> T {
+        self.ready().handshake_output_eval(coefficients)
+    }
+
+    pub(crate) fn ole_input(&self) -> Vec<T> {
+        self.preprocessing().ole_input()
+    }
+
+    pub(crate) fn set_r_power_shares(&mut self, ole_output: Vec<T>) {
+        let prover = self.take_preprocessing().set_r_power_shares(ole_output);
+        self.state = Some(State::AwaitingD(prover));
+    }
+
+    pub(crate) fn degree(&self) -> usize {
+        match self.state() {
+            State::Preprocessing(prover) => prover.degree,
+            State::AwaitingD(prover) => prover.degree,
+            State::Ready(prover) => prover.degree,
+        }
+    }
+
+    pub(crate) fn strategy(&self) -> PowerStrategy {
+        match self.state() {
+            State::Preprocessing(prover) => prover.strategy,
+            State::AwaitingD(prover) => prover.strategy,
+            State::Ready(prover) => prover.strategy,
+        }
+    }
+
+    pub(crate) fn set_x1(&mut self, x1: T) {
+        match self.state.as_mut().expect("the prover has aborted") {
+            State::Preprocessing(prover) => prover.x1 = x1,
+            State::AwaitingD(prover) => prover.x1 = x1,
+            State::Ready(prover) => prover.x1 = x1,
+        }
+    }
+
+    /// Replaces the mask `r1`, which is only possible before the OLE input is computed.
+    pub(crate) fn set_r1(&mut self, r1: T) {
+        match self.state.as_mut() {
+            Some(State::Preprocessing(prover)) => prover.r1 = r1,
+            _ => panic!("the OLE has already been run"),
+        }
+    }
+
+    /// Returns the powers of `d`, or an empty slice before `d` is set.
+    pub(crate) fn d_powers(&self) -> &[T] {
+        match &self.state {
+            Some(State::Ready(prover)) => &prover.state.d_powers,
+            _ => &[],
+        }
+    }
+
+    pub(crate) fn ready(&self) -> &typestate::Prover<T, Ready<T>> {
+        match &self.state {
+            Some(State::Ready(prover)) => prover,
+            _ => panic!("d has not been set"),
+        }
+    }
+
+    fn state(&self) -> &State<T> {
+        self.state.as_ref().expect("the prover has aborted")
+    }
+
+    fn preprocessing(&self) -> &typestate::Prover<T, Preprocessing> {
+        match &self.state {
+            Some(State::Preprocessing(prover)) => prover,
+            _ => panic!("the OLE has already been run"),
+        }
+    }
+
+    fn take_preprocessing(&mut self) -> typestate::Prover<T, Preprocessing> {
+        match self.state.take() {
+            Some(State::Preprocessing(prover)) => prover,
+            _ => panic!("the OLE has already been run"),
+        }
+    }
+}
+
+impl<T: Characteristic> From<typestate::Prover<T, Preprocessing>> for Prover<T> {
+    fn from(prover: typestate::Prover<T, Preprocessing>) -> Self {
+        Self {
+            state: Some(State::Preprocessing(prover)),
+        }
+    }
+}
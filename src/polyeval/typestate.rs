@@ -0,0 +1,279 @@
+//! A typestate API for the polynomial evaluation, which enforces the order of the protocol steps
+//! at compile time.
+//!
+//! Every step consumes the party in its current state and returns it in the next state, so
+//! evaluating the polynomial before `d` is known, or setting `d` twice, does not compile. This is
+//! the implementation of the protocol. [`super::Prover`] and [`super::Verifier`] wrap it for
+//! callers which keep a party behind a mutable reference.
+
+use super::{Characteristic, PowerStrategy};
+use crate::func::{
+    ole::Ole,
+    power_ole::{PowerOle, PowerOleError},
+    Role,
+};
+use mpz_share_conversion_core::fields::compute_product_repeated;
+use rand::thread_rng;
+
+/// The party still needs to run the OLE.
+#[derive(Debug)]
+pub struct Preprocessing;
+
+/// The party has its OLE output and waits for the opened `d`.
+#[derive(Debug)]
+pub struct AwaitingD<T> {
+    pub(crate) r_power_shares: Vec<T>,
+}
+
+/// The party holds the shares of the powers of `x` and can evaluate polynomials.
+#[derive(Debug)]
+pub struct Ready<T> {
+    pub(crate) d_powers: Vec<T>,
+    pub(crate) xi: Vec<T>,
+}
+
+#[derive(Debug)]
+pub struct Prover<T: Characteristic, S = Preprocessing> {
+    pub(crate) degree: usize,
+    pub(crate) strategy: PowerStrategy,
+    pub(crate) x1: T,
+    pub(crate) r1: T,
+    pub(crate) state: S,
+}
+
+impl<T: Characteristic> Prover<T, Preprocessing> {
+    pub fn new(degree: usize, x1: T) -> Self {
+        Self::new_with_strategy(degree, x1, PowerStrategy::default())
+    }
+
+    pub fn new_with_strategy(degree: usize, x1: T, strategy: PowerStrategy) -> Self {
+        assert!(
+            T::IS_TWO || strategy == PowerStrategy::Full,
+            "{strategy:?} needs a field of characteristic 2"
+        );
+
+        let mut rng = thread_rng();
+        let r1 = T::rand(&mut rng);
+
+        Self {
+            degree,
+            strategy,
+            x1,
+            r1,
+            state: Preprocessing,
+        }
+    }
+
+    pub fn preprocess_ole_input(&self, ole: &mut Ole<T>) {
+        ole.input(Role::Sender, self.ole_input())
+    }
+
+    pub fn preprocess_ole_output(self, ole: &mut Ole<T>) -> Prover<T, AwaitingD<T>> {
+        let ole_output = ole.output(Role::Sender);
+        self.set_r_power_shares(ole_output)
+    }
+
+    pub fn preprocess_power_ole_input(&self, ole: &mut PowerOle<T>) {
+        ole.input(Role::Sender, self.ole_input())
+    }
+
+    pub fn preprocess_power_ole_output(
+        self,
+        ole: &mut PowerOle<T>,
+    ) -> Result<Prover<T, AwaitingD<T>>, PowerOleError> {
+        let ole_output = ole.output(Role::Sender)?;
+        Ok(self.set_r_power_shares(ole_output))
+    }
+
+    pub(crate) fn ole_input(&self) -> Vec<T> {
+        let mut r1_powers = vec![T::one()];
+        compute_product_repeated(&mut r1_powers, self.r1, self.degree);
+
+        self.strategy
+            .ole_exponents(self.degree)
+            .into_iter()
+            .map(|i| r1_powers[i])
+            .collect()
+    }
+
+    pub(crate) fn set_r_power_shares(self, ole_output: Vec<T>) -> Prover<T, AwaitingD<T>> {
+        let r_power_shares = self
+            .strategy
+            .r_power_shares(Role::Sender, ole_output, self.degree);
+
+        self.transition(AwaitingD { r_power_shares })
+    }
+}
+
+impl<T: Characteristic> Prover<T, AwaitingD<T>> {
+    /// Returns the share of `d`.
+    ///
+    /// For degree 0 no power of `x` is needed, so there is no mask `r` and the share is 0.
+    pub fn handshake_a_open_d(&self) -> T {
+        if self.degree == 0 {
+            return T::zero();
+        }
+        self.x1 + -self.state.r_power_shares[1]
+    }
+
+    /// Sets the opened `d` and computes the shares of the powers of `x`.
+    pub fn handshake_a_set_di(self, d: T) -> Prover<T, Ready<T>> {
+        let mut d_powers = vec![T::one(), d];
+        compute_product_repeated(&mut d_powers, d, self.degree);
+
+        let xi = self
+            .strategy
+            .x_power_shares(&d_powers, &self.state.r_power_shares, self.degree);
+
+        self.transition(Ready { d_powers, xi })
+    }
+}
+
+impl<T: Characteristic> Prover<T, Ready<T>> {
+    /// Returns the share of the polynomial with the given coefficients, where `coefficients[i]`
+    /// belongs to `x^i`.
+    pub fn handshake_output_eval(&self, coefficients: &[T]) -> T {
+        // The constant term is only added by the prover
+        let mut res = coefficients.first().copied().unwrap_or(T::zero());
+
+        for (i, coefficient) in coefficients.iter().skip(1).enumerate() {
+            res = res + *coefficient * self.state.xi[i];
+        }
+        res
+    }
+}
+
+impl<T: Characteristic, S> Prover<T, S> {
+    fn transition<U>(self, state: U) -> Prover<T, U> {
+        Prover {
+            degree: self.degree,
+            strategy: self.strategy,
+            x1: self.x1,
+            r1: self.r1,
+            state,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Verifier<T: Characteristic, S = Preprocessing> {
+    pub(crate) degree: usize,
+    pub(crate) strategy: PowerStrategy,
+    pub(crate) x2: T,
+    pub(crate) r2: T,
+    pub(crate) state: S,
+}
+
+impl<T: Characteristic> Verifier<T, Preprocessing> {
+    pub fn new(degree: usize, x2: T) -> Self {
+        Self::new_with_strategy(degree, x2, PowerStrategy::default())
+    }
+
+    pub fn new_with_strategy(degree: usize, x2: T, strategy: PowerStrategy) -> Self {
+        assert!(
+            T::IS_TWO || strategy == PowerStrategy::Full,
+            "{strategy:?} needs a field of characteristic 2"
+        );
+
+        let mut rng = thread_rng();
+        let r2 = T::rand(&mut rng);
+
+        Self {
+            degree,
+            strategy,
+            x2,
+            r2,
+            state: Preprocessing,
+        }
+    }
+
+    pub fn preprocess_ole_input(&self, ole: &mut Ole<T>) {
+        ole.input(Role::Receiver, self.ole_input())
+    }
+
+    pub fn preprocess_ole_output(self, ole: &mut Ole<T>) -> Verifier<T, AwaitingD<T>> {
+        let ole_output = ole.output(Role::Receiver);
+        self.set_r_power_shares(ole_output)
+    }
+
+    pub fn preprocess_power_ole_input(&self, ole: &mut PowerOle<T>) {
+        ole.input(Role::Receiver, self.ole_input())
+    }
+
+    pub fn preprocess_power_ole_output(
+        self,
+        ole: &mut PowerOle<T>,
+    ) -> Result<Verifier<T, AwaitingD<T>>, PowerOleError> {
+        let ole_output = ole.output(Role::Receiver)?;
+        Ok(self.set_r_power_shares(ole_output))
+    }
+
+    pub(crate) fn ole_input(&self) -> Vec<T> {
+        let mut r2_powers = vec![T::one()];
+        compute_product_repeated(&mut r2_powers, self.r2, self.degree);
+
+        self.strategy
+            .ole_exponents(self.degree)
+            .into_iter()
+            .map(|i| r2_powers[i])
+            .collect()
+    }
+
+    pub(crate) fn set_r_power_shares(self, ole_output: Vec<T>) -> Verifier<T, AwaitingD<T>> {
+        let r_power_shares = self
+            .strategy
+            .r_power_shares(Role::Receiver, ole_output, self.degree);
+
+        self.transition(AwaitingD { r_power_shares })
+    }
+}
+
+impl<T: Characteristic> Verifier<T, AwaitingD<T>> {
+    /// Returns the share of `d`.
+    ///
+    /// For degree 0 no power of `x` is needed, so there is no mask `r` and the share is 0.
+    pub fn handshake_a_open_d(&self) -> T {
+        if self.degree == 0 {
+            return T::zero();
+        }
+        self.x2 + -self.state.r_power_shares[1]
+    }
+
+    /// Sets the opened `d` and computes the shares of the powers of `x`.
+    pub fn handshake_a_set_di(self, d: T) -> Verifier<T, Ready<T>> {
+        let mut d_powers = vec![T::one(), d];
+        compute_product_repeated(&mut d_powers, d, self.degree);
+
+        let xi = self
+            .strategy
+            .x_power_shares(&d_powers, &self.state.r_power_shares, self.degree);
+
+        self.transition(Ready { d_powers, xi })
+    }
+}
+
+impl<T: Characteristic> Verifier<T, Ready<T>> {
+    /// Returns the share of the polynomial with the given coefficients, where `coefficients[i]`
+    /// belongs to `x^i`.
+    pub fn handshake_output_eval(&self, coefficients: &[T]) -> T {
+        // The constant term is only added by the prover
+        let mut res = T::zero();
+
+        for (i, coefficient) in coefficients.iter().skip(1).enumerate() {
+            res = res + *coefficient * self.state.xi[i];
+        }
+        res
+    }
+}
+
+impl<T: Characteristic, S> Verifier<T, S> {
+    fn transition<U>(self, state: U) -> Verifier<T, U> {
+        Verifier {
+            degree: self.degree,
+            strategy: self.strategy,
+            x2: self.x2,
+            r2: self.r2,
+            state,
+        }
+    }
+}
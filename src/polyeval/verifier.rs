@@ -0,0 +1,169 @@
+use super::typestate::{self, AwaitingD, Preprocessing, Ready};
+use super::{Characteristic, PowerStrategy};
+use crate::func::{
+    ole::Ole,
+    power_ole::{PowerOle, PowerOleError},
+};
+
+/// A compatibility layer over [`typestate::Verifier`], which keeps the current state behind a
+/// mutable reference.
+///
+/// Calling a method in the wrong state panics instead of failing to compile.
+#[derive(Debug)]
+pub struct Verifier<T: Characteristic> {
+    state: Option<State<T>>,
+}
+
+#[derive(Debug)]
+enum State<T: Characteristic> {
+    Preprocessing(typestate::Verifier<T, Preprocessing>),
+    AwaitingD(typestate::Verifier<T, AwaitingD<T>>),
+    Ready(typestate::Verifier<T, Ready<T>>),
+}
+
+impl<T: Characteristic> Verifier<T> {
+    pub fn new(degree: usize, x2: T) -> Self {
+        typestate::Verifier::new(degree, x2).into()
+    }
+
+    pub fn new_with_strategy(degree: usize, x2: T, strategy: PowerStrategy) -> Self {
+        typestate::Verifier::new_with_strategy(degree, x2, strategy).into()
+    }
+
+    pub fn preprocess_ole_input(&self, ole: &mut Ole<T>) {
+        self.preprocessing().preprocess_ole_input(ole)
+    }
+
+    pub fn preprocess_ole_output(&mut self, ole: &mut Ole<T>) {
+        let verifier = self.take_preprocessing().preprocess_ole_output(ole);
+        self.state = Some(State::AwaitingD(verifier));
+    }
+
+    pub fn preprocess_power_ole_input(&self, ole: &mut PowerOle<T>) {
+        self.preprocessing().preprocess_power_ole_input(ole)
+    }
+
+    /// Runs the output step of the power OLE.
+    ///
+    /// If the power OLE aborts, the verifier is left without state, like the typestate verifier which
+    /// is consumed by the failed step.
+    pub fn preprocess_power_ole_output(
+        &mut self,
+        ole: &mut PowerOle<T>,
+    ) -> Result<(), PowerOleError> {
+        let verifier = self.take_preprocessing().preprocess_power_ole_output(ole)?;
+        self.state = Some(State::AwaitingD(verifier));
+        Ok(())
+    }
+
+    pub fn handshake_a_open_d(&self) -> T {
+        match &self.state {
+            Some(State::AwaitingD(verifier)) => verifier.handshake_a_open_d(),
+            _ => panic!("the OLE has not been run"),
+        }
+    }
+
+    /// Sets the opened `d` and computes the shares of the powers of `x`.
+    pub fn handshake_a_set_di(&mut self, d: T) {
+        let verifier = match self.state.take() {
+            Some(State::AwaitingD(verifier)) => verifier.handshake_a_set_di(d),
+            _ => panic!("the OLE has not been run"),
+        };
+        self.state = Some(State::Ready(verifier));
+    }
+
+    /// Checks that the shares of the powers of `x` are available.
+    ///
+    /// They are already computed by [`Self::handshake_a_set_di`], so this step only remains for
+    /// callers of the earlier API.
+    pub fn handshake_a_set_xi(&mut self) {
+        self.ready();
+    }
+
+    /// Returns the share of the polynomial with the given coefficients, where `coefficients[i]`
+    /// belongs to `x^i`.
+    pub fn handshake_output_eval(&self, coefficients: &[T]) -> T {
+        self.ready().handshake_output_eval(coefficients)
+    }
+
+    pub(crate) fn ole_input(&self) -> Vec<T> {
+        self.preprocessing().ole_input()
+    }
+
+    pub(crate) fn set_r_power_shares(&mut self, ole_output: Vec<T>) {
+        let verifier = self.take_preprocessing().set_r_power_shares(ole_output);
+        self.state = Some(State::AwaitingD(verifier));
+    }
+
+    pub(crate) fn degree(&self) -> usize {
+        match self.state() {
+            State::Preprocessing(verifier) => verifier.degree,
+            State::AwaitingD(verifier) => verifier.degree,
+            State::Ready(verifier) => verifier.degree,
+        }
+    }
+
+    pub(crate) fn strategy(&self) -> PowerStrategy {
+        match self.state() {
+            State::Preprocessing(verifier) => verifier.strategy,
+            State::AwaitingD(verifier) => verifier.strategy,
+            State::Ready(verifier) => verifier.strategy,
+        }
+    }
+
+    pub(crate) fn set_x2(&mut self, x2: T) {
+        match self.state.as_mut().expect("the verifier has aborted") {
+            State::Preprocessing(verifier) => verifier.x2 = x2,
+            State::AwaitingD(verifier) => verifier.x2 = x2,
+            State::Ready(verifier) => verifier.x2 = x2,
+        }
+    }
+
+    /// Replaces the mask `r2`, which is only possible before the OLE input is computed.
+    pub(crate) fn set_r2(&mut self, r2: T) {
+        match self.state.as_mut() {
+            Some(State::Preprocessing(verifier)) => verifier.r2 = r2,
+            _ => panic!("the OLE has already been run"),
+        }
+    }
+
+    pub(crate) fn ready(&self) -> &typestate::Verifier<T, Ready<T>> {
+        match &self.state {
+            Some(State::Ready(verifier)) => verifier,
+            _ => panic!("d has not been set"),
+        }
+    }
+
+    pub(crate) fn ready_mut(&mut self) -> &mut typestate::Verifier<T, Ready<T>> {
+        match &mut self.state {
+            Some(State::Ready(verifier)) => verifier,
+            _ => panic!("d has not been set"),
+        }
+    }
+
+    fn state(&self) -> &State<T> {
+        self.state.as_ref().expect("the verifier has aborted")
+    }
+
+    fn preprocessing(&self) -> &typestate::Verifier<T, Preprocessing> {
+        match &self.state {
+            Some(State::Preprocessing(verifier)) => verifier,
+            _ => panic!("the OLE has already been run"),
+        }
+    }
+
+    fn take_preprocessing(&mut self) -> typestate::Verifier<T, Preprocessing> {
+        match self.state.take() {
+            Some(State::Preprocessing(verifier)) => verifier,
+            _ => panic!("the OLE has already been run"),
+        }
+    }
+}
+
+impl<T: Characteristic> From<typestate::Verifier<T, Preprocessing>> for Verifier<T> {
+    fn from(verifier: typestate::Verifier<T, Preprocessing>) -> Self {
+        Self {
+            state: Some(State::Preprocessing(verifier)),
+        }
+    }
+}
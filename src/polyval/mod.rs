@@ -0,0 +1,136 @@
+//! This module implements the POLYVAL function of AES-GCM-SIV (RFC 8452) on top of the two-party
+//! GHASH protocol.
+//!
+//! POLYVAL works in the same field as GHASH, but uses a different reduction convention and byte
+//! order. By RFC 8452, Appendix A, we have
+//!
+//! `POLYVAL(H, X_1, ..., X_n) = ByteReverse(GHASH(mulX_GHASH(ByteReverse(H)), ByteReverse(X_1), ..., ByteReverse(X_n)))`
+//!
+//! All of these maps are linear, so every party converts its shares locally and the shares of `H`
+//! are expanded into shares of its powers with the OLE-based protocol from [`crate::ghash`].
+
+mod prover;
+mod verifier;
+
+use crate::func::ole::Ole;
+use crate::ghash::{block_to_gf2_128, gf2_128_to_block};
+use mpz_share_conversion_core::fields::gf2_128::Gf2_128;
+pub use prover::Prover;
+pub use verifier::Verifier;
+
+/// Returns the POLYVAL of `blocks`, which are given in the byte order of RFC 8452
+pub fn polyval(blocks: &[[u8; 16]], prover: &mut Prover, verifier: &mut Verifier) -> [u8; 16] {
+    let mut ole = Ole::default();
+
+    prover.preprocess_ole_input(&mut ole);
+    verifier.preprocess_ole_input(&mut ole);
+
+    prover.preprocess_ole_output(&mut ole);
+    verifier.preprocess_ole_output(&mut ole);
+
+    let d1 = prover.handshake_a_open_d();
+    let d2 = verifier.handshake_a_open_d();
+    let d = d1 + d2;
+
+    prover.handshake_a_set_di(d);
+    verifier.handshake_a_set_di(d);
+
+    prover.handshake_a_set_hi();
+    verifier.handshake_a_set_hi();
+
+    let polyval1 = prover.handshake_output_polyval(blocks);
+    let polyval2 = verifier.handshake_output_polyval(blocks);
+
+    std::array::from_fn(|i| polyval1[i] ^ polyval2[i])
+}
+
+/// Converts a share of the POLYVAL key into a share of the corresponding GHASH key.
+fn key_to_ghash(h: [u8; 16]) -> Gf2_128 {
+    // Multiplication with x, i.e. mulX_GHASH
+    block_to_ghash(h) * Gf2_128::new(2)
+}
+
+/// Converts POLYVAL blocks into GHASH blocks.
+///
+/// The GHASH protocol multiplies the first block with the lowest power of `H`, while POLYVAL
+/// multiplies the first block with the highest power, so the order is reversed.
+fn blocks_to_ghash(blocks: &[[u8; 16]]) -> Vec<Gf2_128> {
+    blocks
+        .iter()
+        .rev()
+        .map(|&block| block_to_ghash(block))
+        .collect()
+}
+
+/// Maps a POLYVAL block to the field element of its byte-reversed GHASH block.
+fn block_to_ghash(mut block: [u8; 16]) -> Gf2_128 {
+    block.reverse();
+    block_to_gf2_128(block)
+}
+
+/// Maps a GHASH output share back to a POLYVAL output share, inverting [`block_to_ghash`].
+fn ghash_to_block(share: Gf2_128) -> [u8; 16] {
+    let mut block = gf2_128_to_block(share);
+    block.reverse();
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ghash::PowerStrategy, test_utils::hex};
+    use rand::{thread_rng, Rng};
+
+    #[test]
+    fn test_polyval() {
+        // RFC 8452, Appendix A
+        let h = hex("25629347589242761d31f826ba4b757b").try_into().unwrap();
+        let blocks = [
+            hex("4f4f95668c83dfb6401762bb2d01a262").try_into().unwrap(),
+            hex("d1a24ddd2721d006bbe45f20d3c9f362").try_into().unwrap(),
+        ];
+        let expected: [u8; 16] = hex("f7a3b47b846119fae5b7866cf5e5b77e").try_into().unwrap();
+
+        for strategy in [PowerStrategy::Full, PowerStrategy::FreeSquaring] {
+            let (h1, h2) = share(h);
+
+            let mut prover = Prover::new_with_strategy(blocks.len(), h1, strategy);
+            let mut verifier = Verifier::new_with_strategy(blocks.len(), h2, strategy);
+
+            assert_eq!(polyval(&blocks, &mut prover, &mut verifier), expected);
+        }
+    }
+
+    #[test]
+    fn test_polyval_aes_gcm_siv() {
+        // RFC 8452, Appendix C.1, 8 bytes of plaintext
+        let h = hex("d9b360279694941ac5dbc6987ada7377").try_into().unwrap();
+        let blocks = [
+            hex("01000000000000000000000000000000").try_into().unwrap(),
+            hex("00000000000000004000000000000000").try_into().unwrap(),
+        ];
+        let expected: [u8; 16] = hex("eb93b7740962c5e49d2a90a7dc5cec74").try_into().unwrap();
+
+        let (h1, h2) = share(h);
+
+        let mut prover = Prover::new(blocks.len(), h1);
+        let mut verifier = Verifier::new(blocks.len(), h2);
+
+        assert_eq!(polyval(&blocks, &mut prover, &mut verifier), expected);
+    }
+
+    #[test]
+    fn test_block_conversion() {
+        let mut rng = thread_rng();
+        let block: [u8; 16] = rng.gen();
+
+        assert_eq!(ghash_to_block(block_to_ghash(block)), block);
+    }
+
+    fn share(h: [u8; 16]) -> ([u8; 16], [u8; 16]) {
+        let h1: [u8; 16] = thread_rng().gen();
+        let h2 = std::array::from_fn(|i| h[i] ^ h1[i]);
+
+        (h1, h2)
+    }
+}
@@ -0,0 +1,37 @@
+use super::{blocks_to_ghash, ghash_to_block, key_to_ghash};
+use crate::func::ole::Ole;
+use crate::ghash::{self, PowerStrategy};
+use delegate::delegate;
+use mpz_share_conversion_core::fields::gf2_128::Gf2_128;
+
+#[derive(Debug)]
+pub struct Verifier {
+    pub(crate) inner: ghash::Verifier,
+}
+
+impl Verifier {
+    pub fn new(block_num: usize, h2: [u8; 16]) -> Self {
+        Self::new_with_strategy(block_num, h2, PowerStrategy::default())
+    }
+
+    pub fn new_with_strategy(block_num: usize, h2: [u8; 16], strategy: PowerStrategy) -> Self {
+        Self {
+            inner: ghash::Verifier::new_with_strategy(block_num, key_to_ghash(h2), strategy),
+        }
+    }
+
+    pub fn handshake_output_polyval(&self, blocks: &[[u8; 16]]) -> [u8; 16] {
+        let blocks = blocks_to_ghash(blocks);
+        ghash_to_block(self.inner.handshake_output_ghash(&blocks))
+    }
+
+    delegate! {
+        to self.inner {
+            pub fn preprocess_ole_input(&self, ole: &mut Ole<Gf2_128>);
+            pub fn preprocess_ole_output(&mut self, ole: &mut Ole<Gf2_128>);
+            pub fn handshake_a_open_d(&self) -> Gf2_128;
+            pub fn handshake_a_set_di(&mut self, d: Gf2_128);
+            pub fn handshake_a_set_hi(&mut self);
+        }
+    }
+}
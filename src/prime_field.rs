@@ -0,0 +1,182 @@
+//! A macro for the prime fields of this crate, which are implemented with `ark_ff`.
+
+/// Defines the prime field `$name` with the given modulus and generator, which implements
+/// [`Field`](mpz_share_conversion_core::fields::Field) and the bit traits of `itybity`.
+///
+/// `$limbs` is the number of 64-bit limbs of the modulus and `$bits` its bit length. The field
+/// gets an infallible `new` from a `u128`, which needs a modulus larger than `2^128`.
+macro_rules! prime_field {
+    (
+        $(#[$attr:meta])*
+        $name:ident,
+        modulus = $modulus:literal,
+        generator = $generator:literal,
+        limbs = $limbs:literal,
+        bits = $bits:literal $(,)?
+    ) => {
+        // The derive macro checks for an `asm` feature, which this crate does not have
+        #[allow(unexpected_cfgs)]
+        mod config {
+            use ark_ff::MontConfig;
+
+            #[derive(MontConfig)]
+            #[modulus = $modulus]
+            #[generator = $generator]
+            pub(super) struct Config;
+        }
+
+        type Fp = ark_ff::Fp<ark_ff::MontBackend<config::Config, $limbs>, $limbs>;
+
+        $(#[$attr])*
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub struct $name {
+            inner: Fp,
+        }
+
+        impl $name {
+            #[doc = concat!("Create a new `", stringify!($name), "` from a `u128`.")]
+            ///
+            /// Unlike `P256::new` of `mpz_share_conversion_core`, this cannot fail, because every
+            /// `u128` is smaller than the modulus.
+            pub fn new(value: u128) -> Self {
+                Self {
+                    inner: Fp::from(value),
+                }
+            }
+
+            #[doc = concat!(
+                "Create a new `",
+                stringify!($name),
+                "` from little-endian bytes, which are reduced modulo the field prime."
+            )]
+            pub fn from_le_bytes_mod_order(bytes: &[u8]) -> Self {
+                use ark_ff::PrimeField;
+
+                Self {
+                    inner: Fp::from_le_bytes_mod_order(bytes),
+                }
+            }
+        }
+
+        impl mpz_share_conversion_core::fields::Field for $name {
+            const BIT_SIZE: u32 = $bits;
+
+            fn zero() -> Self {
+                Self::new(0)
+            }
+
+            fn one() -> Self {
+                Self::new(1)
+            }
+
+            fn two_pow(rhs: u32) -> Self {
+                (0..rhs).fold(Self::one(), |acc, _| acc + acc)
+            }
+
+            fn inverse(self) -> Self {
+                use ark_ff::Field;
+
+                Self {
+                    inner: self.inner.inverse().expect("No inverse for 0"),
+                }
+            }
+
+            fn to_le_bytes(&self) -> Vec<u8> {
+                use ark_ff::{BigInteger, PrimeField};
+
+                let mut bytes = self.inner.into_bigint().to_bytes_le();
+                bytes.truncate(($bits as usize).div_ceil(8));
+                bytes
+            }
+
+            fn to_be_bytes(&self) -> Vec<u8> {
+                let mut bytes = self.to_le_bytes();
+                bytes.reverse();
+                bytes
+            }
+        }
+
+        impl rand::distributions::Distribution<$name> for rand::distributions::Standard {
+            fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> $name {
+                $name {
+                    inner: ark_ff::UniformRand::rand(rng),
+                }
+            }
+        }
+
+        impl std::ops::Add for $name {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self::Output {
+                Self {
+                    inner: self.inner + rhs.inner,
+                }
+            }
+        }
+
+        impl std::ops::Mul for $name {
+            type Output = Self;
+
+            fn mul(self, rhs: Self) -> Self::Output {
+                Self {
+                    inner: self.inner * rhs.inner,
+                }
+            }
+        }
+
+        impl std::ops::Neg for $name {
+            type Output = Self;
+
+            fn neg(self) -> Self::Output {
+                Self { inner: -self.inner }
+            }
+        }
+
+        impl itybity::BitLength for $name {
+            const BITS: usize = $bits;
+        }
+
+        impl itybity::GetBit<itybity::Lsb0> for $name {
+            fn get_bit(&self, index: usize) -> bool {
+                use ark_ff::{BigInteger, PrimeField};
+
+                self.inner.into_bigint().get_bit(index)
+            }
+        }
+
+        impl itybity::GetBit<itybity::Msb0> for $name {
+            fn get_bit(&self, index: usize) -> bool {
+                use ark_ff::{BigInteger, PrimeField};
+                use itybity::BitLength;
+
+                self.inner.into_bigint().get_bit(Self::BITS - 1 - index)
+            }
+        }
+
+        impl itybity::FromBitIterator for $name {
+            fn from_lsb0_iter(iter: impl IntoIterator<Item = bool>) -> Self {
+                use ark_ff::{BigInteger, PrimeField};
+
+                let bits: Vec<bool> = iter.into_iter().collect();
+
+                Self {
+                    inner: Fp::from_bigint(ark_ff::BigInt::from_bits_le(&bits))
+                        .expect("Value is not in the field"),
+                }
+            }
+
+            fn from_msb0_iter(iter: impl IntoIterator<Item = bool>) -> Self {
+                use ark_ff::{BigInteger, PrimeField};
+
+                let bits: Vec<bool> = iter.into_iter().collect();
+
+                Self {
+                    inner: Fp::from_bigint(ark_ff::BigInt::from_bits_be(&bits))
+                        .expect("Value is not in the field"),
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use prime_field;